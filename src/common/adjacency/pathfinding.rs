@@ -1,38 +1,177 @@
-use std::collections::{VecDeque,HashSet};
-use std::hash::Hash;
-
-pub fn breadth_first_search<'a, N: Eq + Hash + Copy, IN: Iterator<Item = &'a N>, C: Ord + Copy>(
-    start: &'a N,
-    mut adjacency: impl FnMut(&N) -> IN,
-    mut heuristic: impl FnMut(&N) -> Option<C>
-) -> Vec<N> {
-    let Some(mut min_distance) = heuristic(&start) else { return vec![*start] };
-    let mut closest_index: usize = 0;
-    let mut index: usize = 0;
-    let mut stack: Vec<(N, usize, usize)> = vec![(*start, 0, 0)];
-    let mut visited: HashSet<N> = HashSet::new();
-    visited.insert(*start);
-    'outer: while let Some(&(node,depth,_)) = stack.get(index) {
-        for adjacent in adjacency(&node) {
-            if visited.contains(&adjacent) { continue; }
-            stack.push((*adjacent, depth + 1, index));
-
-            let Some(distance) = heuristic(&adjacent) else {
-                closest_index = stack.len() - 1;
-                break 'outer
-            };
-            if distance < min_distance {
-                closest_index = stack.len() - 1;
-                min_distance = distance;
-            }
-            visited.insert(*adjacent);
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::cmp::Ordering;
+use bevy::utils::FloatOrd;
+use super::TraversableGraph;
+
+struct OpenNode<N> {
+    estimate: f32,
+    index: N,
+}
+impl<N> PartialEq for OpenNode<N> {
+    fn eq(&self, other: &Self) -> bool { FloatOrd(self.estimate) == FloatOrd(other.estimate) }
+}
+impl<N> Eq for OpenNode<N> {}
+impl<N> PartialOrd for OpenNode<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+impl<N> Ord for OpenNode<N> {
+    fn cmp(&self, other: &Self) -> Ordering { FloatOrd(other.estimate).cmp(&FloatOrd(self.estimate)) }
+}
+
+///Shortest distances and parent links towards the closest source.
+pub struct DistanceField<N> {
+    nodes: HashMap<N, (f32, N)>,
+}
+impl<N: std::hash::Hash + Eq + Copy> DistanceField<N> {
+    pub fn distance(&self, index: N) -> Option<f32> { self.nodes.get(&index).map(|&(distance, _)|distance) }
+    pub fn iter(&self) -> impl Iterator<Item = (N, f32)> + '_ {
+        self.nodes.iter().map(|(&index, &(distance, _))|(index, distance))
+    }
+    ///Nodes from the source up to the index.
+    pub fn path_to(&self, index: N) -> Option<Vec<N>> {
+        let mut out: VecDeque<N> = VecDeque::new();
+        let mut index = index;
+        loop {
+            let &(_, parent) = self.nodes.get(&index)?;
+            out.push_front(index);
+            if parent == index { break Some(Vec::from(out)) }
+            index = parent;
+        }
+    }
+    ///Nodes from the index down to the closest source.
+    pub fn path_from(&self, index: N) -> Option<Vec<N>> {
+        self.path_to(index).map(|mut path|{ path.reverse(); path })
+    }
+}
+
+fn weighted_search<G: TraversableGraph>(
+    graph: &G,
+    sources: impl IntoIterator<Item = G::Index>,
+    mut cost: impl FnMut(G::Index, G::Index) -> Option<f32>,
+    mut heuristic: impl FnMut(G::Index) -> f32,
+    mut goal: impl FnMut(G::Index) -> bool,
+    limit: Option<f32>,
+) -> (DistanceField<G::Index>, Option<G::Index>) {
+    let mut nodes: HashMap<G::Index, (f32, G::Index)> = HashMap::new();
+    let mut open: BinaryHeap<OpenNode<G::Index>> = BinaryHeap::new();
+    let mut closed: HashSet<G::Index> = HashSet::new();
+    for source in sources {
+        nodes.insert(source, (0.0, source));
+        open.push(OpenNode { estimate: heuristic(source), index: source });
+    }
+    while let Some(OpenNode { index, .. }) = open.pop() {
+        if !closed.insert(index) { continue; }
+        if goal(index) { return (DistanceField { nodes }, Some(index)); }
+        let distance = nodes[&index].0;
+        for &adjacent in graph.adjacent(index).iter() {
+            if closed.contains(&adjacent) { continue; }
+            let Some(step) = cost(index, adjacent) else { continue };
+            let next_distance = distance + step;
+            if limit.map_or(false, |limit|next_distance > limit) { continue; }
+            if nodes.get(&adjacent).map_or(false, |&(prev_distance, _)|prev_distance <= next_distance) { continue; }
+            nodes.insert(adjacent, (next_distance, index));
+            open.push(OpenNode { estimate: next_distance + heuristic(adjacent), index: adjacent });
         }
-        index += 1;
     }
-    let mut out: VecDeque<N> = VecDeque::with_capacity(stack[closest_index].1 + 1);
-    loop {
-        let (node,_depth,parent) = stack[closest_index];
-        out.push_front(node);
-        closest_index = if parent != closest_index { parent }else{ break Vec::from(out) };
+    (DistanceField { nodes }, None)
+}
+
+///Weighted search from start to target with an admissible heuristic.
+///Edges with no cost are impassable. When the target is unreachable
+///the path leads to the explored node with the lowest heuristic instead.
+pub fn a_star_search<G: TraversableGraph>(
+    graph: &G,
+    start: G::Index,
+    target: G::Index,
+    cost: impl FnMut(G::Index, G::Index) -> Option<f32>,
+    mut heuristic: impl FnMut(G::Index) -> f32,
+) -> Vec<G::Index> {
+    let (field, reached) = weighted_search(graph, [start], cost, &mut heuristic, |index|index == target, None);
+    let closest = reached.unwrap_or_else(||field.iter()
+        .min_by_key(|&(index, distance)|(FloatOrd(heuristic(index)), FloatOrd(distance)))
+        .map_or(start, |(index, _)|index));
+    field.path_to(closest).unwrap()
+}
+
+///Weighted search from start until the first node satisfying the goal.
+pub fn dijkstra_search<G: TraversableGraph>(
+    graph: &G,
+    start: G::Index,
+    cost: impl FnMut(G::Index, G::Index) -> Option<f32>,
+    goal: impl FnMut(G::Index) -> bool,
+) -> Option<Vec<G::Index>> {
+    let (field, reached) = weighted_search(graph, [start], cost, |_|0.0, goal, None);
+    field.path_to(reached?)
+}
+
+///Distances from the closest of multiple sources, optionally up to a maximum distance.
+pub fn dijkstra_distance_field<G: TraversableGraph>(
+    graph: &G,
+    sources: impl IntoIterator<Item = G::Index>,
+    cost: impl FnMut(G::Index, G::Index) -> Option<f32>,
+    limit: Option<f32>,
+) -> DistanceField<G::Index> {
+    weighted_search(graph, sources, cost, |_|0.0, |_|false, limit).0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct AdjacencyList(Vec<Vec<usize>>);
+    impl TraversableGraph for AdjacencyList {
+        type Index = usize;
+        fn adjacent(&self, index: usize) -> &[usize] { &self.0[index] }
+    }
+    fn grid_graph(width: usize, height: usize) -> AdjacencyList {
+        AdjacencyList((0..width * height).map(|i|{
+            let (x, y) = (i % width, i / width);
+            let mut adjacent = Vec::new();
+            if x > 0 { adjacent.push(i - 1); }
+            if x + 1 < width { adjacent.push(i + 1); }
+            if y > 0 { adjacent.push(i - width); }
+            if y + 1 < height { adjacent.push(i + width); }
+            adjacent
+        }).collect())
+    }
+
+    #[test]
+    pub fn a_star_avoids_expensive_tiles(){
+        let graph = grid_graph(3, 3);
+        let cost = |_: usize, to: usize|if to == 4 { Some(10.0) }else{ Some(1.0) };
+        let heuristic = |index: usize|((index % 3).abs_diff(1) + (index / 3).abs_diff(2)) as f32;
+        let path = a_star_search(&graph, 1, 7, cost, heuristic);
+        assert_eq!(path.first(), Some(&1));
+        assert_eq!(path.last(), Some(&7));
+        assert_eq!(path.len(), 5);
+        assert!(!path.contains(&4));
+    }
+
+    #[test]
+    pub fn a_star_unreachable_closest(){
+        let graph = grid_graph(3, 1);
+        let path = a_star_search(&graph, 0, 2, |_, to|if to == 2 { None }else{ Some(1.0) }, |index|2.0 - index as f32);
+        assert_eq!(path, vec![0, 1]);
+    }
+
+    #[test]
+    pub fn dijkstra_early_exit(){
+        let graph = grid_graph(4, 4);
+        let path = dijkstra_search(&graph, 0, |_, _|Some(1.0), |index|index == 5).unwrap();
+        assert_eq!(path.len(), 3);
+        assert!(dijkstra_search(&graph, 0, |_, to|if to == 1 || to == 4 { None }else{ Some(1.0) }, |index|index == 5).is_none());
+    }
+
+    #[test]
+    pub fn multi_source_distance_field(){
+        let graph = grid_graph(5, 1);
+        let field = dijkstra_distance_field(&graph, [0, 4], |_, _|Some(1.0), None);
+        assert_eq!(field.distance(2), Some(2.0));
+        assert_eq!(field.distance(3), Some(1.0));
+        assert_eq!(field.path_from(3), Some(vec![3, 4]));
+
+        let field = dijkstra_distance_field(&graph, [0], |_, _|Some(1.0), Some(2.0));
+        assert_eq!(field.distance(2), Some(2.0));
+        assert_eq!(field.distance(3), None);
     }
-}
\ No newline at end of file
+}
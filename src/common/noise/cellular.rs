@@ -0,0 +1,79 @@
+use bevy::math::{Vec3,Vec3A,IVec3};
+use super::sampler::HeightSampler;
+use super::hash::*;
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum CellularReturn {
+    ///Distance to the closest feature point.
+    #[default] Distance,
+    ///Random value of the closest feature point cell.
+    Cell,
+}
+
+///Worley noise, matches wrapped_worley31 in noise_cellular.wgsl.
+#[derive(Default)]
+pub struct CellularNoise {
+    pub period: Vec3A,
+    pub return_type: CellularReturn,
+}
+
+impl CellularNoise {
+    fn wrap(&self, cell: IVec3) -> IVec3 {
+        let period = self.period.as_ivec3();
+        IVec3::new(
+            if period.x > 0 { cell.x.rem_euclid(period.x) }else{ cell.x },
+            if period.y > 0 { cell.y.rem_euclid(period.y) }else{ cell.y },
+            if period.z > 0 { cell.z.rem_euclid(period.z) }else{ cell.z },
+        )
+    }
+}
+
+impl HeightSampler<Vec3A> for CellularNoise {
+    fn sample(&self, coordinate: Vec3A, seed: u32) -> f32 {
+        let i0 = coordinate.floor().as_ivec3();
+        let f0 = coordinate - i0.as_vec3a();
+        let mut min_distance = f32::MAX;
+        let mut closest = IVec3::ZERO;
+        for x in -1..=1 { for y in -1..=1 { for z in -1..=1 {
+            let offset = IVec3::new(x, y, z);
+            let cell = self.wrap(i0 + offset);
+            let point = 0.5 + 0.5 * Vec3A::new(
+                hash31(cell.x, cell.y, cell.z, seed).normalize(),
+                hash31(cell.x, cell.y, cell.z, seed.wrapping_add(1)).normalize(),
+                hash31(cell.x, cell.y, cell.z, seed.wrapping_add(2)).normalize(),
+            );
+            let distance = (f0 - (point + offset.as_vec3a())).length_squared();
+            if distance < min_distance {
+                min_distance = distance;
+                closest = cell;
+            }
+        } } }
+        match self.return_type {
+            CellularReturn::Distance => min_distance.sqrt().min(1.0) * 2.0 - 1.0,
+            CellularReturn::Cell => hash31(closest.x, closest.y, closest.z, seed.wrapping_add(3)).normalize(),
+        }
+    }
+}
+
+impl HeightSampler<Vec3> for CellularNoise {
+    fn sample(&self, coordinate: Vec3, seed: u32) -> f32 { self.sample(Vec3A::from(coordinate), seed) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test] pub fn sample_cellular_noise(){
+        let noise = CellularNoise { period: Vec3A::splat(4.0), ..Default::default() };
+        for i in 0..64 {
+            let coordinate = Vec3A::new(i as f32 * 0.37, i as f32 * 0.21, i as f32 * -0.13);
+            let value = noise.sample(coordinate, 7);
+            assert!(value >= -1.0 && value <= 1.0);
+            assert!((value - noise.sample(coordinate + Vec3A::splat(4.0), 7)).abs() < 1e-4);
+        }
+        let noise = CellularNoise { return_type: CellularReturn::Cell, ..Default::default() };
+        for i in 0..64 {
+            let value = noise.sample(Vec3A::new(i as f32 * 0.37, 0.5, 1.5), 3);
+            assert!(value >= -1.0 && value < 1.0);
+        }
+    }
+}
@@ -9,6 +9,7 @@ pub use helpers::WeightTable;
 pub mod value;
 pub mod perlin;
 pub mod simplex;
+pub mod cellular;
 
 use bevy::prelude::*;
 use bevy::asset::HandleId;
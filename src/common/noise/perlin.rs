@@ -1,4 +1,4 @@
-use bevy::math::{Vec2,Vec3A,Vec4};
+use bevy::math::{Vec2,Vec3,Vec3A,Vec4};
 use super::sampler::HeightSampler;
 use super::helpers::*;
 use super::hash::{hash21,hash31,hash41};
@@ -87,6 +87,10 @@ impl HeightSampler<Vec3A> for PerlinNoise {
     }
 }
 
+impl HeightSampler<Vec3> for PerlinNoise {
+    fn sample(&self, coordinate: Vec3, seed: u32) -> f32 { self.sample(Vec3A::from(coordinate), seed) }
+}
+
 const GRADIENTS_2D: [Vec2; 16] = [
     Vec2::new(0.98078525, 0.19509032),
     Vec2::new(0.8314696, 0.55557024),
@@ -6,10 +6,10 @@ pub trait HeightSampler<T> {
     fn sample(&self, coordinate: T, seed: u32) -> f32;
 }
 
-#[derive(PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Default, Debug)]
 pub enum FractalType {
     None,
-    Fbm,
+    #[default] Fbm,
     Ridged,
     PingPong(f32)
 }
@@ -1,4 +1,4 @@
-use bevy::math::{Vec2,Vec3A,Vec4,IVec2,IVec3,Mat2,Mat3A};
+use bevy::math::{Vec2,Vec3,Vec3A,Vec4,IVec2,IVec3,Mat2,Mat3A};
 use super::sampler::HeightSampler;
 use super::perlin::{gradient_dot_2d,gradient_dot_3d};
 
@@ -128,4 +128,10 @@ impl HeightSampler<Vec3A> for SimplexNoise<Vec3A> {
 
         return 39.5 * Vec4::dot(w*w*w, gradient);
     }
-}
\ No newline at end of file
+}
+
+impl HeightSampler<Vec3> for SimplexNoise<Vec3> {
+    fn sample(&self, coordinate: Vec3, seed: u32) -> f32 {
+        SimplexNoise { period: Vec3A::from(self.period) }.sample(Vec3A::from(coordinate), seed)
+    }
+}
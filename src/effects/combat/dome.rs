@@ -54,11 +54,19 @@ pub fn animate_dome_effect(
         }
     }
     for (entity, effect, military, supply, transform) in query_unit.p0().iter_mut() {
-        let MilitaryBinding::Area { .. } = military else { continue };
+        let target_intensity = match military {
+            MilitaryBinding::Area { .. } => 1.0,
+            MilitaryBinding::Barrier { stored, capacity, .. } => (*stored as f32 / *capacity as f32).min(1.0),
+            _ => continue
+        };
         let radius = military.radius() * supply.range_multipler();
 
         if let Some(mut effect) = effect {
-            effect.intensity = (effect.intensity + time.delta_seconds() / effect.intro_duration).min(1.0);
+            effect.intensity = if effect.intensity < target_intensity {
+                (effect.intensity + time.delta_seconds() / effect.intro_duration).min(target_intensity)
+            } else {
+                (effect.intensity - time.delta_seconds() / effect.outro_duration).max(target_intensity)
+            };
 
             if let Ok(mut transform) = query_transform.get_mut(effect.barrier) {
                 transform.scale = Vec3::ZERO.lerp(Vec3::ONE, effect.ease.calculate(effect.intensity));
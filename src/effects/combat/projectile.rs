@@ -22,11 +22,14 @@ pub fn animate_projectile_effect(
     mut meshes: ResMut<Assets<Mesh>>,
 
     query_effect: Query<(Entity, Ref<TrajectoryEffect>, &Handle<ProjectileTrailMaterial>)>,
+    fixed_time: Res<FixedTime>,
 ){
+    let fraction = fixed_time.accumulated().as_secs_f32() / fixed_time.period.as_secs_f32();
     for (_entity, effect, material) in query_effect.iter() {
         let material = materials.get_mut(material).unwrap();
-        material.uv_transform.y = lerp(1.0, -material.uv_transform.w + 1.0, effect.intro.percent());
-        material.uv_transform.y = lerp(material.uv_transform.y, -material.uv_transform.w, effect.outro.percent());
+        let (intro, outro) = effect.calculate(fraction);
+        material.uv_transform.y = lerp(1.0, -material.uv_transform.w + 1.0, intro);
+        material.uv_transform.y = lerp(material.uv_transform.y, -material.uv_transform.w, outro);
     }
 
     for event in events.iter() {
@@ -127,11 +130,14 @@ pub fn animate_projectile_effect(
 }
 
 pub fn reorient_targeting_systems(
-    query_unit: Query<(Entity, &EntityLookupTable, &MilitaryBinding, &GlobalTransform), Changed<MilitaryBinding>>,
+    fixed_time: Res<FixedTime>,
+    query_unit: Query<(Entity, &EntityLookupTable, &MilitaryBinding, &GlobalTransform)>,
     mut query_transform: Query<(&Parent, &mut Transform, &GlobalTransform)>,
 ){
+    let fraction = fixed_time.accumulated().as_secs_f32() / fixed_time.period.as_secs_f32();
     for (_entity, lookup, military, transform) in query_unit.iter() {
-        let MilitaryBinding::Trajectory { key, orientation, axis, .. } = military else { continue };
+        let MilitaryBinding::Trajectory { key, axis, .. } = military else { continue };
+        let Some(orientation) = military.calculate_orientation(fraction) else { continue };
 
         let Some(entity) = lookup.get(key) else { continue };
         let Ok(parent_transform) = query_transform.get_component::<Parent>(*entity)
@@ -143,6 +149,6 @@ pub fn reorient_targeting_systems(
 
         let local_rotation = axis.as_ref().map_or(Quat::IDENTITY, |mat| Quat::from_mat3(mat));
 
-        local_transform.rotation = parent_rotation.mul_quat(orientation.clone()).mul_quat(local_rotation);
+        local_transform.rotation = parent_rotation.mul_quat(orientation).mul_quat(local_rotation);
     }
 }
\ No newline at end of file
@@ -36,12 +36,15 @@ pub fn animate_tentacle_effect(
     )>,
     query_unit: Query<&MilitaryBinding>,
     transforms: Query<Ref<GlobalTransform>>,
+    fixed_time: Res<FixedTime>,
     mut meshes: ResMut<Assets<Mesh>>,
 ){
+    let fraction = fixed_time.accumulated().as_secs_f32() / fixed_time.period.as_secs_f32();
     for (
         source, target, trajectory, mut spline, mut deformation, mut uniform
     ) in query_effect.p1().iter_mut() {
-        let percent = (trajectory.intro.percent() - trajectory.outro.percent()).clamp(0.0, 1.0);
+        let (intro, outro) = trajectory.calculate(fraction);
+        let percent = (intro - outro).clamp(0.0, 1.0);
         deformation.offset = percent - 1.0;
         uniform.color.set_r(percent);
 
@@ -0,0 +1,16 @@
+use bevy::prelude::*;
+use crate::logic::{Agent, AllianceTable, GridTileIndex, GridVisibility};
+
+pub fn animate_unit_condition_concealed(
+    alliances: Res<AllianceTable>,
+    query_grid: Query<&GridVisibility>,
+    mut query_unit: Query<(&Agent, &Parent, &GridTileIndex, &mut Visibility)>,
+){
+    let mask = alliances.vision_mask(&Agent::Player);
+    for (agent, parent, tile_index, mut visibility) in query_unit.iter_mut() {
+        let concealed = !alliances.is_allied(&Agent::Player, agent) && query_grid.get(parent.get())
+            .map_or(false, |grid|!grid.is_visible(**tile_index, mask));
+        let next_visibility = if concealed { Visibility::Hidden }else{ Visibility::Inherited };
+        if !visibility.eq(&next_visibility) { *visibility = next_visibility; }
+    }
+}
@@ -4,7 +4,7 @@ use crate::common::loader::AssetBundle;
 use crate::common::animation::ease::BezierCurve;
 use crate::scene::ModelAssetBundle;
 use crate::materials::ModelEffectLayeredMaterial;
-use crate::logic::{Agent, UnderConstruction};
+use crate::logic::{Agent, UnderConstruction, QueuedConstruction};
 use super::membership::MembershipSettings;
 
 #[derive(Component)]
@@ -19,10 +19,13 @@ pub struct ConstructionEffect {
 pub struct ConstructionEffectOptions {
     pub ease: BezierCurve,
     pub dissolve_scale: Vec3,
+    ///Glass opacity of constructions waiting in the queue.
+    pub queued_alpha: f32,
 }
 impl Default for ConstructionEffectOptions { fn default() -> Self {Self {
     ease: BezierCurve::new(0.0,0.5,1.0,0.5),
     dissolve_scale: Vec3::new(4.0, 36.0, 4.0),
+    queued_alpha: 0.4,
 } } }
 
 
@@ -36,7 +39,7 @@ pub fn animate_construction(
     model_asset_bundle: Res<AssetBundle<ModelAssetBundle>>,
     mut set: ParamSet<(
         Query<(Entity, &Children, &Agent, &UnderConstruction, &GlobalTransform), Without<ConstructionEffect>>,
-        Query<(&UnderConstruction, &ConstructionEffect)>,
+        Query<(&UnderConstruction, &ConstructionEffect, Option<&QueuedConstruction>)>,
         Query<(Entity, &Children, &ConstructionEffect), Without<UnderConstruction>>,
     )>,
     query_scene: Query<(Entity, &Aabb), With<bevy::scene::SceneInstance>>,
@@ -96,12 +99,13 @@ pub fn animate_construction(
         }
         commands.entity(entity).insert(effect);
     }
-    for (construction, effect) in set.p1().iter() {
+    for (construction, effect, queued) in set.p1().iter() {
         let progress = options.ease.calculate(construction.calculate(fraction));
         let mut material = effect_materials.get_mut(&effect.model_handle).unwrap();
         material.alpha_threshold = progress * (1.0 + material.dissolve_offset.x);
         let mut glass_material = materials.get_mut(&effect.glass_handle).unwrap();
-        glass_material.base_color = glass_material.base_color.with_a(progress * effect.glass_alpha);
+        let glass_progress = if queued.is_some() { options.queued_alpha }else{ progress };
+        glass_material.base_color = glass_material.base_color.with_a(glass_progress * effect.glass_alpha);
     }
     for (entity, children, effect) in set.p2().iter() {
         commands.entity(entity).remove::<ConstructionEffect>();
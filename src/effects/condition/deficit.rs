@@ -2,7 +2,7 @@ use bevy::prelude::*;
 use crate::common::loader::AssetBundle;
 use crate::effects::animation::AnimationSettings;
 use crate::materials::{ColorUniform, ScanlineEffectMaterial, ModelEffectLayeredMaterial};
-use crate::logic::{GroupLink, MapGrid, GridTileIndex, MatterBinding, UnderConstruction, Suspended};
+use crate::logic::{GroupLink, MapGrid, GridTileIndex, MatterBinding, UnderConstruction, QueuedConstruction, Suspended};
 use crate::scene::EffectAssetBundle;
 use crate::effects::outline::BorderOutline;
 
@@ -22,7 +22,7 @@ pub fn animate_unit_condition_deficit(
     mut query_unit: Query<(
         Entity, &Parent, &GridTileIndex, Option<&mut DeficitEffectAnimation>,
         Option<&MatterBinding>, Option<&UnderConstruction>, Option<&Suspended>, Option<&GroupLink>
-    )>,
+    ), Without<QueuedConstruction>>,
     children: Query<&Children>,
     mut query: Query<&mut ColorUniform, Or<(
         With<Handle<ModelEffectLayeredMaterial>>,
@@ -15,4 +15,6 @@ pub use deficit::*;
 mod destruct;
 pub use destruct::*;
 mod fabricate;
-pub use fabricate::*;
\ No newline at end of file
+pub use fabricate::*;
+mod concealed;
+pub use concealed::*;
\ No newline at end of file
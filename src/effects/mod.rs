@@ -60,6 +60,7 @@ pub struct EffectsPlugin; impl Plugin for EffectsPlugin {
             condition::animate_unit_condition_damaged,
             condition::animate_unit_condition_deficit,
             condition::animate_unit_condition_fabricated,
+            condition::animate_unit_condition_concealed,
             condition::animate_construction.after(condition::apply_unit_membership),
             condition::animate_reconstruction,
             condition::animate_destruction,
@@ -1,29 +1,31 @@
 use bevy::prelude::*;
 use crate::common::loader::AssetBundle;
-use crate::logic::{Agent, MapGrid, GridTileIndex, GroupLink, PriorityOrder, GlobalEconomy};
-use crate::logic::{Suspended, ConstructionEvent, Integrity, UnitDirective, FollowingPath, FabricationGate};
-use crate::scene::{GlobalState, UnitBlueprint, ModelAssetBundle};
+use crate::logic::{Agent, MapGrid, GridTileIndex, GroupLink, NetworkGroupList, PriorityOrder, GlobalEconomy};
+use crate::logic::{Suspended, QueuedConstruction, ConstructionEvent, Integrity, UnitDirective, FollowingPath, FabricationGate, UnitFabrication};
+use crate::scene::{GlobalState, StageSelection, UnitBlueprint, ModelAssetBundle};
 use super::{InteractionEvent, ViewMode, ActionSelector};
 
 pub fn construct_unit(
     commands: &mut Commands,
     parent: Entity, grid: &MapGrid,
-    model_bundle: &AssetBundle<ModelAssetBundle>,
+    model_bundle: Option<&AssetBundle<ModelAssetBundle>>,
     blueprints: &Assets<UnitBlueprint>,
     (blueprint_handle, agent, index): (Handle<UnitBlueprint>, Agent, usize),
 ) -> Entity {
     let selected = &grid.tiles[index];
     let blueprint = blueprints.get(&blueprint_handle).unwrap();
     
-    let model = model_bundle.model_from(&blueprint, commands);
     let entity = commands.spawn((
         GridTileIndex(index),
         SpatialBundle::from_transform(selected.transform.clone().with_scale(selected.transform.scale * blueprint.scale)),
         agent.clone(),
         blueprint_handle.clone(),
         blueprint.integrity.clone()
-    )).insert_children(0, &[model])
-    .set_parent(parent).id();
+    )).set_parent(parent).id();
+    if let Some(model_bundle) = model_bundle {
+        let model = model_bundle.model_from(&blueprint, commands);
+        commands.entity(entity).insert_children(0, &[model]);
+    }
     blueprint.apply(commands.entity(entity), false);
     entity
 }
@@ -32,7 +34,7 @@ pub fn construct_structure(
     commands: &mut Commands,
     construction_events: &mut EventWriter<ConstructionEvent>,
     parent: Entity, grid: &mut MapGrid,
-    model_bundle: &AssetBundle<ModelAssetBundle>,
+    model_bundle: Option<&AssetBundle<ModelAssetBundle>>,
     blueprints: &Assets<UnitBlueprint>,
     (blueprint_handle, agent, index, order): (Handle<UnitBlueprint>, Agent, usize, u64),
     immediate: bool
@@ -49,11 +51,13 @@ pub fn construct_structure(
         )).set_parent(parent).id()
     };
 
-    let model = model_bundle.model_from(&blueprint, commands);
     commands.entity(entity)
-        .insert_children(0, &[model])
         .insert(agent.clone())
         .insert(blueprint_handle.clone());
+    if let Some(model_bundle) = model_bundle {
+        let model = model_bundle.model_from(&blueprint, commands);
+        commands.entity(entity).insert_children(0, &[model]);
+    }
     blueprint.apply(commands.entity(entity), !immediate);
     selected.reference = Some(entity);
     if immediate {
@@ -71,38 +75,68 @@ pub fn process_interaction_event(
     mut exit: EventWriter<bevy::app::AppExit>,
     mut commands: Commands,
     mut global: ResMut<GlobalEconomy>,
+    mut selection: ResMut<StageSelection>,
     mut mode: ResMut<ViewMode>,
     mut previous_mode: Local<ViewMode>,
     mut construction_events: EventWriter<ConstructionEvent>,
     mut interaction_events: EventReader<InteractionEvent>,
 
     blueprints: Res<Assets<UnitBlueprint>>,
-    model_bundle: Res<AssetBundle<ModelAssetBundle>>,
+    model_bundle: Option<Res<AssetBundle<ModelAssetBundle>>>,
 
     mut query_grid: Query<&mut MapGrid>,
+    query_groups: Query<&NetworkGroupList>,
     mut query_unit: ParamSet<(
         Query<(&Parent, &mut PriorityOrder, Option<&Suspended>)>,
         Query<&mut Integrity>,
         Query<&Handle<UnitBlueprint>>,
+        Query<(&Parent, &GridTileIndex, &GroupLink), With<QueuedConstruction>>,
     )>
 ){
     for event in interaction_events.iter() {
         match event {
             &InteractionEvent::Construct(agent, parent, index, ref blueprint_handle) => {
                 let Ok(mut grid) = query_grid.get_mut(parent) else { continue };
-                if let Some(previous) = grid.tiles[index].reference {
+                let previous = grid.tiles[index].reference;
+                if let Some(previous) = previous {
                     commands.entity(previous).remove::<FabricationGate>();
                 }
-                construct_structure(
+                let entity = construct_structure(
                     &mut commands, &mut construction_events,
-                    parent, &mut grid, &model_bundle, &blueprints,
+                    parent, &mut grid, model_bundle.as_deref(), &blueprints,
                     (blueprint_handle.clone(), agent, index, global.next_priority()), false
                 );
+                if previous.is_none() {
+                    commands.entity(entity).insert(QueuedConstruction);
+                }
+            },
+            &InteractionEvent::Cancel(entity) => {
+                let Ok((parent, index)) = query_unit.p3().get(entity)
+                    .map(|(parent, tile_index, _)|(parent.get(), **tile_index)) else { continue };
+                let Ok(mut grid) = query_grid.get_mut(parent) else { continue };
+                grid.tiles[index].clear();
+                construction_events.send(ConstructionEvent::Dismantle { entity, parent, index });
+                commands.entity(entity).despawn_recursive();
+            },
+            &InteractionEvent::Reorder(entity, forward) => {
+                let Ok((parent, Some(group_index))) = query_unit.p3().get(entity)
+                    .map(|(parent, _, link)|(parent.get(), **link)) else { continue };
+                let Ok(groups) = query_groups.get(parent) else { continue };
+                let Some(queue) = groups.get(group_index).map(|group|&group.queue) else { continue };
+                let Some(position) = queue.iter().position(|&queued|queued == entity) else { continue };
+                let Some(&adjacent) = (if forward { position.checked_sub(1) }else{ Some(position + 1) })
+                    .and_then(|position|queue.get(position)) else { continue };
+
+                let mut query_unit = query_unit.p0();
+                let Ok([(_, mut order, _), (_, mut adjacent_order, _)]) = query_unit.get_many_mut([entity, adjacent]) else { continue };
+                std::mem::swap(&mut order.0, &mut adjacent_order.0);
+                let Ok(mut grid) = query_grid.get_mut(parent) else { continue };
+                grid.set_changed();
             },
             &InteractionEvent::Deconstruct(entity) => {
                 let mut query_unit = query_unit.p1();
                 let Ok(mut integrity) = query_unit.get_mut(entity) else { continue };
-                integrity.apply_damage(i32::MAX);
+                integrity.destroy();
             },
             &InteractionEvent::Toggle(entity) => {
                 let mut query_unit = query_unit.p0();
@@ -116,6 +150,13 @@ pub fn process_interaction_event(
                     commands.entity(entity).insert(Suspended);
                 }
             },
+            &InteractionEvent::Prioritize(entity, level) => {
+                let query_unit = query_unit.p0();
+                let Ok((parent, _, _)) = query_unit.get(entity) else { continue };
+                let Ok(mut grid) = query_grid.get_mut(parent.get()) else { continue };
+                commands.entity(entity).insert(level);
+                grid.set_changed();
+            },
             InteractionEvent::Execute(entity, selector, flags) => {
                 let Some(blueprint) = query_unit.p2().get(*entity).ok()
                     .and_then(|handle|blueprints.get(handle)) else { continue };
@@ -134,7 +175,7 @@ pub fn process_interaction_event(
                         if let Some(path) = path {
                             commands.entity(*entity).insert(FabricationGate {
                                 path: path.nodes.clone(), filter: *flags,
-                                limit: if *flags == 1 { 0 }else{ 1 }, ..Default::default()
+                                limit: if *flags == UnitFabrication::CIVILIAN { 1 }else{ 0 }, ..Default::default()
                             });
                         } else {
                             commands.entity(*entity).remove::<FabricationGate>();
@@ -157,9 +198,14 @@ pub fn process_interaction_event(
                 *mode = std::mem::take(&mut previous_mode);
                 next_state.set(GlobalState::from(mode.as_ref()));
             },
-            InteractionEvent::Start(stage) => {
+            &InteractionEvent::Start(stage) => {
+                selection.0 = stage;
                 next_state.set(GlobalState::Running);
             },
+            &InteractionEvent::Edit(stage) => {
+                selection.0 = stage;
+                next_state.set(GlobalState::Editor);
+            },
             InteractionEvent::Exit => {
                 exit.send(bevy::app::AppExit);
             }
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 use crate::scene::UnitBlueprint;
-use crate::logic::Agent;
+use crate::logic::{Agent, PriorityLevel};
 use super::{ViewMode, ActionSelector};
 
 #[derive(Component, Deref, DerefMut, Clone)]
@@ -11,8 +11,15 @@ pub enum InteractionEvent {
     Construct(Agent, Entity, usize, Handle<UnitBlueprint>),
     Toggle(Entity),
     Deconstruct(Entity),
+    ///Remove a queued construction before it has started.
+    Cancel(Entity),
+    ///Move a queued construction ahead or behind its neighbour in the queue.
+    Reorder(Entity, bool),
+    Prioritize(Entity, PriorityLevel),
     EnterMode(Option<ViewMode>),
     Execute(Entity, ActionSelector, u8),
     Start(usize),
+    ///Open the stage in the editor.
+    Edit(usize),
     Exit,
 }
\ No newline at end of file
@@ -8,15 +8,20 @@ mod selection;
 pub use selection::*;
 mod transition;
 pub use transition::*;
+mod replay;
+pub use replay::*;
 
 pub mod path;
 
 use bevy::prelude::*;
 use crate::common::animation::AnimationSet;
+use crate::scene::GlobalState;
 pub struct InteractionPlugin; impl Plugin for InteractionPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<ViewMode>();
         app.add_event::<InteractionEvent>();
+        app.add_plugin(ReplayPlugin);
+        app.add_system(view::reset_view_mode.in_schedule(OnEnter(GlobalState::Menu)));
 
         app.add_system(transition::schedule_state_transitions::<transition::SelectionState>
             .before(AnimationSet::StateMachine));
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
-use crate::common::adjacency::breadth_first_search;
-use crate::logic::MapGrid;
+use crate::common::adjacency::a_star_search;
+use crate::logic::{MapGrid, TerrainVariantTable};
 use super::{GridSelection, ViewMode, ActionSelector};
 
 #[derive(Deref, DerefMut, PartialEq, Clone, Default)]
@@ -24,6 +24,7 @@ impl ActionPath {
 
 pub fn select_action_path(
     mut mode: ResMut<ViewMode>,
+    terrain: Res<TerrainVariantTable>,
     query_grid: Query<(&MapGrid, Ref<GridSelection>)>
 ){
     let ViewMode::Action(_target_index, origin_index, selector) = mode.bypass_change_detection() else { return };
@@ -37,16 +38,15 @@ pub fn select_action_path(
     };
 
     let prev_index = selected_path.as_ref().and_then(|path|path.nodes.last()).unwrap_or(&origin_index);
-    let nodes = breadth_first_search(
-        prev_index,
-        |&index|grid.graph.neighbors(index).unwrap().iter()
-        .filter(|&i|origin_index.eq(i) || match passthrough {
-            false => grid.tiles[*i].is_empty(),
-            true => grid.tiles[*i].is_empty() || grid.tiles[*i].reference.is_some()
-        }),
-        |index| if selection.0.eq(index) { None } else {
-        Some(bevy::utils::FloatOrd(grid.tiles[*index].transform.translation.distance_squared(grid.tiles[selection.0].transform.translation)))
-    });
+    let target_position = grid.tiles[selection.0].transform.translation;
+    let nodes = a_star_search(
+        &grid.graph, *prev_index, selection.0,
+        |prev, index|if origin_index == index || match passthrough {
+            false => grid.tiles[index].is_empty(),
+            true => grid.tiles[index].is_empty() || grid.tiles[index].reference.is_some()
+        } { Some(grid.movement_cost(&terrain, prev, index)) } else { None },
+        |index|grid.tiles[index].transform.translation.distance(target_position)
+    );
 
     if let Some(path) = selected_path.as_mut() {
         let (tail, head) = nodes.iter()
@@ -0,0 +1,226 @@
+use bevy::prelude::*;
+use bevy::app::AppExit;
+use std::path::{Path, PathBuf};
+use crate::common::loader::{AssetBundle, LoadingState};
+use crate::logic::{Agent, PriorityLevel, MapGrid, GridTileIndex, GridAreaIndex, SimulationTick};
+use crate::scene::{GlobalState, UnitBlueprint, BlueprintAssetBundle, StageSelection};
+use super::{InteractionEvent, ActionSelector, path::ActionPath, process_interaction_event};
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Debug)]
+pub enum ReplayAction {
+    Construct { agent: Agent, area: usize, tile: usize, key: String },
+    Toggle { area: usize, tile: usize },
+    Deconstruct { area: usize, tile: usize },
+    Cancel { area: usize, tile: usize },
+    Reorder { area: usize, tile: usize, forward: bool },
+    Prioritize { area: usize, tile: usize, level: PriorityLevel },
+    Execute { area: usize, tile: usize, target: bool, path: Option<Vec<usize>>, flags: u8 },
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Debug)]
+pub struct ReplayEntry {
+    pub tick: u64,
+    pub action: ReplayAction,
+}
+
+///Player issued interactions keyed by the fixed update tick they were applied on.
+///AI decisions are not stored, they are reproduced from the seed.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default, Debug)]
+pub struct Replay {
+    pub stage: String,
+    pub seed: u64,
+    pub entries: Vec<ReplayEntry>,
+}
+impl Replay {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let bytes = std::fs::read(path)?;
+        Ok(serde_ron::de::from_bytes(&bytes)?)
+    }
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn std::error::Error>> {
+        let text = serde_ron::ser::to_string_pretty(self, Default::default())?;
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+#[derive(Resource, Clone, Default)]
+pub struct ReplayRecorder {
+    pub path: Option<PathBuf>,
+    pub replay: Replay,
+}
+
+#[derive(Resource, Clone, Default)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    cursor: usize,
+}
+impl From<Replay> for ReplayPlayback {
+    fn from(replay: Replay) -> Self { Self { replay, cursor: 0 } }
+}
+impl ReplayPlayback {
+    pub fn has_ended(&self) -> bool { self.cursor >= self.replay.entries.len() }
+}
+
+pub struct ReplayPlugin; impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayRecorder>();
+        app.add_systems((
+            begin_replay_recording.run_if(not(resource_exists::<ReplayPlayback>())).run_if(not(in_state(GlobalState::Editor))),
+            begin_replay_playback.run_if(resource_exists::<ReplayPlayback>()),
+        ).after(crate::logic::reset_simulation_tick).in_schedule(OnExit(GlobalState::Menu)));
+        app.add_system(save_replay_recording.in_schedule(OnEnter(GlobalState::Menu)));
+        app.add_system(save_replay_recording.run_if(on_event::<AppExit>()).in_base_set(CoreSet::Last));
+
+        app.add_system(select_replay_stage
+            .run_if(resource_exists::<ReplayPlayback>())
+            .run_if(in_state(GlobalState::Menu))
+            .run_if(in_state(LoadingState::Running))
+            .after(process_interaction_event)
+            .in_base_set(CoreSet::PreUpdate));
+        app.add_system(playback_interaction_events
+            .run_if(resource_exists::<ReplayPlayback>())
+            .run_if(in_state(GlobalState::Running))
+            .run_if(in_state(LoadingState::Running))
+            .before(process_interaction_event)
+            .in_base_set(CoreSet::PreUpdate));
+        app.add_system(record_interaction_events
+            .run_if(not(resource_exists::<ReplayPlayback>()))
+            .run_if(in_state(LoadingState::Running))
+            .after(process_interaction_event)
+            .in_base_set(CoreSet::PreUpdate));
+    }
+}
+
+fn begin_replay_recording(
+    tick: Res<SimulationTick>,
+    asset_server: Res<AssetServer>,
+    blueprint_bundle: Res<AssetBundle<BlueprintAssetBundle>>,
+    selection: Res<StageSelection>,
+    mut recorder: ResMut<ReplayRecorder>,
+){
+    recorder.replay = Replay {
+        stage: blueprint_bundle.stages.get(**selection).and_then(|handle|asset_server.get_handle_path(handle))
+            .map_or(String::new(), |path|path.path().to_string_lossy().into_owned()),
+        seed: tick.seed,
+        entries: Vec::new(),
+    };
+}
+
+fn select_replay_stage(
+    asset_server: Res<AssetServer>,
+    blueprint_bundle: Res<AssetBundle<BlueprintAssetBundle>>,
+    playback: Res<ReplayPlayback>,
+    mut selection: ResMut<StageSelection>,
+){
+    let Some(index) = blueprint_bundle.stages.iter().position(|handle|asset_server.get_handle_path(handle)
+        .map_or(false, |path|path.path().to_string_lossy() == playback.replay.stage)) else { return };
+    if **selection != index { **selection = index; }
+}
+
+fn begin_replay_playback(
+    mut tick: ResMut<SimulationTick>,
+    mut playback: ResMut<ReplayPlayback>,
+){
+    tick.seed = playback.replay.seed;
+    playback.cursor = 0;
+}
+
+fn save_replay_recording(recorder: Res<ReplayRecorder>){
+    let Some(path) = recorder.path.as_ref() else { return };
+    if recorder.replay.stage.is_empty() { return; }
+    if let Err(error) = recorder.replay.save(path) {
+        error!("failed to save replay {:?}: {}", path, error);
+    }
+}
+
+pub fn record_interaction_events(
+    tick: Res<SimulationTick>,
+    mut recorder: ResMut<ReplayRecorder>,
+    mut events: EventReader<InteractionEvent>,
+    blueprints: Res<Assets<UnitBlueprint>>,
+    query_grid: Query<&GridAreaIndex>,
+    query_unit: Query<(&Parent, &GridTileIndex, &Agent)>,
+){
+    for event in events.iter() {
+        let unit = |entity: &Entity|query_unit.get(*entity).ok()
+            .filter(|(_, _, agent)|!matches!(agent, Agent::AI(_)))
+            .and_then(|(parent, tile_index, _)|query_grid.get(parent.get()).ok()
+                .map(|area_index|(**area_index, **tile_index)));
+        let action = match event {
+            InteractionEvent::Construct(Agent::AI(_), ..) => None,
+            InteractionEvent::Construct(agent, parent, index, handle) => query_grid.get(*parent).ok()
+                .zip(blueprints.get(handle))
+                .map(|(area_index, blueprint)|ReplayAction::Construct {
+                    agent: *agent, area: **area_index, tile: *index, key: blueprint.key.clone()
+                }),
+            InteractionEvent::Toggle(entity) => unit(entity)
+                .map(|(area, tile)|ReplayAction::Toggle { area, tile }),
+            InteractionEvent::Deconstruct(entity) => unit(entity)
+                .map(|(area, tile)|ReplayAction::Deconstruct { area, tile }),
+            InteractionEvent::Cancel(entity) => unit(entity)
+                .map(|(area, tile)|ReplayAction::Cancel { area, tile }),
+            &InteractionEvent::Reorder(entity, forward) => unit(&entity)
+                .map(|(area, tile)|ReplayAction::Reorder { area, tile, forward }),
+            &InteractionEvent::Prioritize(entity, level) => unit(&entity)
+                .map(|(area, tile)|ReplayAction::Prioritize { area, tile, level }),
+            InteractionEvent::Execute(entity, selector, flags) => unit(entity)
+                .map(|(area, tile)|{
+                    let (target, path) = match selector {
+                        ActionSelector::FollowPath(path) => (false, path),
+                        ActionSelector::Target(path) => (true, path),
+                    };
+                    let path = path.as_ref().map(|path|path.nodes.clone());
+                    ReplayAction::Execute { area, tile, target, path, flags: *flags }
+                }),
+            _ => None
+        };
+        let Some(action) = action else { continue };
+        recorder.replay.entries.push(ReplayEntry { tick: tick.elapsed, action });
+    }
+}
+
+pub fn playback_interaction_events(
+    tick: Res<SimulationTick>,
+    mut playback: ResMut<ReplayPlayback>,
+    mut events: EventWriter<InteractionEvent>,
+    blueprint_bundle: Res<AssetBundle<BlueprintAssetBundle>>,
+    query_grid: Query<(Entity, &GridAreaIndex, &MapGrid)>,
+){
+    while let Some(entry) = playback.replay.entries.get(playback.cursor) {
+        if entry.tick > tick.elapsed { break; }
+        let area = match &entry.action {
+            ReplayAction::Construct { area, .. } |
+            ReplayAction::Toggle { area, .. } |
+            ReplayAction::Deconstruct { area, .. } |
+            ReplayAction::Cancel { area, .. } |
+            ReplayAction::Reorder { area, .. } |
+            ReplayAction::Prioritize { area, .. } |
+            ReplayAction::Execute { area, .. } => *area,
+        };
+        if let Some((parent, _, grid)) = query_grid.iter().find(|(_, area_index, _)|***area_index == area) {
+            let event = match &entry.action {
+                ReplayAction::Construct { agent, tile, key, .. } => Some(InteractionEvent::Construct(
+                    *agent, parent, *tile, blueprint_bundle.find_unit(key).clone()
+                )),
+                ReplayAction::Toggle { tile, .. } => grid.tiles[*tile].reference
+                    .map(InteractionEvent::Toggle),
+                ReplayAction::Deconstruct { tile, .. } => grid.tiles[*tile].reference
+                    .map(InteractionEvent::Deconstruct),
+                ReplayAction::Cancel { tile, .. } => grid.tiles[*tile].reference
+                    .map(InteractionEvent::Cancel),
+                &ReplayAction::Reorder { tile, forward, .. } => grid.tiles[tile].reference
+                    .map(|entity|InteractionEvent::Reorder(entity, forward)),
+                &ReplayAction::Prioritize { tile, level, .. } => grid.tiles[tile].reference
+                    .map(|entity|InteractionEvent::Prioritize(entity, level)),
+                ReplayAction::Execute { tile, target, path, flags, .. } => grid.tiles[*tile].reference
+                    .map(|entity|{
+                        let path = path.as_ref().map(|nodes|ActionPath { nodes: nodes.clone() });
+                        let selector = if *target { ActionSelector::Target(path) }else{ ActionSelector::FollowPath(path) };
+                        InteractionEvent::Execute(entity, selector, *flags)
+                    }),
+            };
+            if let Some(event) = event { events.send(event); }
+        }
+        playback.cursor += 1;
+    }
+}
@@ -7,16 +7,22 @@ use super::path::ActionPath;
 pub enum ViewMode {
     #[default] Menu,
     Default(Agent),
-    Action(Entity, usize, ActionSelector)
+    Action(Entity, usize, ActionSelector),
+    Editor,
 }
 impl From<&ViewMode> for GlobalState {
     fn from(value: &ViewMode) -> Self { match value {
         ViewMode::Menu => GlobalState::Menu,
         ViewMode::Default(_) => GlobalState::Running,
+        ViewMode::Editor => GlobalState::Editor,
         _ => GlobalState::Paused,
     } }
 }
 
+pub fn reset_view_mode(mut mode: ResMut<ViewMode>){
+    *mode = ViewMode::Menu;
+}
+
 #[derive(Clone, PartialEq)]
 pub enum ActionSelector {
     FollowPath(Option<ActionPath>),
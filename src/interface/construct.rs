@@ -1,19 +1,21 @@
 use bevy::prelude::*;
 use crate::common::loader::AssetBundle;
 use crate::interaction::{ViewMode, GridSelection, InteractionEvent, SelectionState};
-use crate::logic::{Agent, GroupLink, MapGrid, NetworkGroupList, UnderConstruction, Suspended};
+use crate::logic::{Agent, AllianceTable, TerrainVariantTable, GroupLink, MapGrid, NetworkGroupList, UnderConstruction, Suspended};
 use crate::scene::{InterfaceAssetBundle, BlueprintAssetBundle, UnitBlueprint};
 use super::layout::OverlayLayout;
 use super::shared::{ControlComponent, ControlComponentDescriptor};
 
+///New structures extend a network of the same agent and can not border networks of opposing agents.
 pub fn validate_construction(
-    blueprint: &UnitBlueprint, unit: Option<&UnitBlueprint>, agent: &Agent,
-    grid: &MapGrid, groups: &NetworkGroupList, tile_index: usize
+    blueprint: &UnitBlueprint, unit: Option<&UnitBlueprint>, agent: &Agent, alliances: &AllianceTable,
+    terrain: &TerrainVariantTable, grid: &MapGrid, groups: &NetworkGroupList, tile_index: usize
 ) -> bool {
     let selected = &grid.tiles[tile_index];
     match (&blueprint.predecessor, unit) {
-        (None, None) => selected.is_empty() && grid.iter_adjacent_groups(tile_index)
-        .any(|group|groups[*group].agent.eq(agent)),
+        (None, None) => selected.is_empty() && terrain.buildable(selected.variant) && grid.iter_adjacent_groups(tile_index)
+        .any(|group|groups[*group].agent.eq(agent)) && grid.iter_adjacent_groups(tile_index)
+        .all(|group|alliances.is_allied(agent, &groups[*group].agent) || alliances.is_neutral(&groups[*group].agent)),
         (Some(key), Some(blueprint)) => blueprint.key.eq(key) && selected.flags & MapGrid::OWNERSHIP != 0,
         _ => false
     }
@@ -27,6 +29,8 @@ pub fn update_construction_menu(
     interface_bundle: Res<AssetBundle<InterfaceAssetBundle>>,
     blueprint_bundle: Res<AssetBundle<BlueprintAssetBundle>>,
     blueprints: Res<Assets<UnitBlueprint>>,
+    alliances: Res<AllianceTable>,
+    terrain: Res<TerrainVariantTable>,
     query_grid: Query<(Entity, &MapGrid, &GridSelection, &NetworkGroupList), Or<(Changed<MapGrid>, Changed<GridSelection>)>>,
     query_unit: Query<(&Agent, &Handle<UnitBlueprint>), (With<GroupLink>, Without<Suspended>, Without<UnderConstruction>)>,
 ){
@@ -44,7 +48,7 @@ pub fn update_construction_menu(
         for handle in blueprint_bundle.unit_blueprints.iter() {
             let Some(option) = blueprints.get(handle) else { continue };
             if !validate_construction(
-                option, unit, global_agent, grid, groups, selection.0
+                option, unit, global_agent, &alliances, &terrain, grid, groups, selection.0
             ) { continue; }
 
             if offset >= components.len() {
@@ -72,4 +76,41 @@ pub fn update_construction_menu(
         components[i].clear_trigger(&mut commands);
         components[i].set_state(&mut commands, SelectionState::Disabled);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::adjacency::Graph;
+    use crate::common::geometry::MeshGeometry;
+    use crate::logic::{MapGridTile, NetworkGroup};
+
+    ///Empty tile next to a network of the player and a network of the other agent.
+    fn validate_next_to(other: Agent, alliances: &AllianceTable) -> bool {
+        let geometry = MeshGeometry { vertices: vec![bevy::math::Vec3A::ZERO; 3], indices: vec![0, 1, 2] };
+        let mut grid = MapGrid { tiles: vec![MapGridTile::default(); 3], graph: Graph::from(&geometry), ..Default::default() };
+        for index in 1..3 {
+            grid.tiles[index].flags = MapGrid::BLOCKER | MapGrid::OWNERSHIP;
+            grid.visited.insert(index, index - 1);
+        }
+        let groups = NetworkGroupList(vec![
+            NetworkGroup { agent: Agent::Player, ..Default::default() },
+            NetworkGroup { agent: other, ..Default::default() },
+        ]);
+        validate_construction(
+            &UnitBlueprint::default(), None, &Agent::Player, alliances,
+            &TerrainVariantTable::default(), &grid, &groups, 0
+        )
+    }
+
+    #[test]
+    pub fn construction_next_to_other_networks(){
+        let alliances = AllianceTable {
+            teams: vec![vec![Agent::Player, Agent::AI(1)]],
+            neutral: vec![Agent::AI(3)],
+        };
+        assert!(validate_next_to(Agent::AI(1), &alliances));
+        assert!(!validate_next_to(Agent::AI(2), &alliances));
+        assert!(validate_next_to(Agent::AI(3), &alliances));
+    }
+}
@@ -63,7 +63,7 @@ pub fn update_unit_controls_action(
                 events.send(InteractionEvent::EnterMode(None));
             }
         },
-        ViewMode::Menu => {}
+        ViewMode::Menu | ViewMode::Editor => {}
     }
     component.clear_trigger(&mut commands);
     component.set_state(&mut commands, SelectionState::Disabled);
@@ -92,13 +92,18 @@ pub fn update_unit_subcontrols_action(
     match mode.as_ref() {
         ViewMode::Action(entity, _index, selector) => {
             if let Ok(fabrication) = query_unit.get(*entity) {
-                let empty = if let ActionSelector::Target(Some(path)) = selector {
+                let flags = if let ActionSelector::Target(Some(path)) = selector {
                     path.last()
-                        .map_or(false,|i|query_grid.get_single()
-                            .map_or(false,|grid|grid.tiles[*i].is_empty()))
-                } else { false };
+                        .map_or(None,|i|query_grid.get_single().ok()
+                            .map(|grid|grid.tiles[*i].flags))
+                } else { None };
+                let valid = match (fabrication.group, flags) {
+                    (UnitFabrication::CIVILIAN, Some(flags)) => flags == 0,
+                    (UnitFabrication::LOGISTIC, Some(flags)) => flags & MapGrid::OWNERSHIP != 0,
+                    _ => false
+                };
 
-                if fabrication.group != UnitFabrication::MILITARY && empty {
+                if valid {
                     component.set_state(&mut commands, SelectionState::Enabled);
                     component.set_label(&mut commands, fabrication.key.clone());
                     component.set_trigger(&mut commands, InteractionEvent::Execute(*entity, selector.clone(), fabrication.group));
@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 use crate::common::loader::AssetBundle;
 use crate::interaction::{ViewMode, GridSelection, InteractionEvent, SelectionState};
-use crate::logic::{Agent, GroupLink};
+use crate::logic::{Agent, GroupLink, QueuedConstruction};
 use crate::scene::InterfaceAssetBundle;
 use super::layout::OverlayLayout;
 use super::shared::{ControlComponent, ControlComponentDescriptor};
@@ -12,7 +12,7 @@ pub fn update_unit_controls_deconstruct(
     mode: Res<ViewMode>,
     layout: Res<OverlayLayout>,
     interface_bundle: Res<AssetBundle<InterfaceAssetBundle>>,
-    query_unit: Query<(Entity, &Agent), (With<GridSelection>, With<GroupLink>)>,
+    query_unit: Query<(Entity, &Agent, Option<&QueuedConstruction>), (With<GridSelection>, With<GroupLink>)>,
 ){
     let component = component.get_or_insert_with(||ControlComponent::new(
         &mut commands, &layout, ControlComponentDescriptor {
@@ -24,10 +24,14 @@ pub fn update_unit_controls_deconstruct(
         }
     ));
     if let ViewMode::Default(global_agent) = mode.as_ref() {
-        if let Ok((entity, agent)) = query_unit.get_single() {
+        if let Ok((entity, agent, queued)) = query_unit.get_single() {
             if global_agent == agent {
                 component.set_state(&mut commands, SelectionState::Enabled);
-                component.set_trigger(&mut commands, InteractionEvent::Deconstruct(entity));
+                component.set_trigger(&mut commands, if queued.is_some() {
+                    InteractionEvent::Cancel(entity)
+                } else {
+                    InteractionEvent::Deconstruct(entity)
+                });
                 return;
             }
         }
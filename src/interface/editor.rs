@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+use crate::common::loader::AssetBundle;
+use crate::interaction::ViewMode;
+use crate::scene::{InterfaceAssetBundle, BlueprintAssetBundle, UnitBlueprint, StageEditor, EDITOR_EXPORT_PATH};
+
+pub fn update_editor_display(
+    mut commands: Commands,
+    mode: Res<ViewMode>,
+    editor: Option<Res<StageEditor>>,
+    interface_bundle: Res<AssetBundle<InterfaceAssetBundle>>,
+    blueprint_bundle: Res<AssetBundle<BlueprintAssetBundle>>,
+    blueprints: Res<Assets<UnitBlueprint>>,
+    mut component: Local<Option<(Entity, Entity)>>,
+    mut label: Local<String>,
+){
+    let Some(editor) = editor.filter(|_|matches!(mode.as_ref(), ViewMode::Editor)) else {
+        if let Some((entity, _)) = component.take() {
+            commands.entity(entity).despawn_recursive();
+            label.clear();
+        }
+        return
+    };
+    let &mut (_, text) = component.get_or_insert_with(||{
+        let text = commands.spawn(TextBundle::default()).id();
+        let entity = commands.spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect { left: Val::Px(16.0), top: Val::Px(16.0), ..Default::default() },
+                ..Default::default()
+            },
+            focus_policy: bevy::ui::FocusPolicy::Pass,
+            z_index: ZIndex::Local(8), ..Default::default()
+        }).add_child(text).id();
+        (entity, text)
+    });
+    let unit = blueprint_bundle.unit_blueprints.get(editor.unit)
+        .and_then(|handle|blueprints.get(handle))
+        .map_or("", |blueprint|blueprint.key.as_str());
+    let mut lines = vec![
+        format!("{} [{:?}]", editor.stage.name, editor.tool),
+        format!("agent {:?}  variant {}  unit {}", editor.agent, editor.variant, unit),
+    ];
+    if let Some(area) = editor.stage.areas.get(editor.area) {
+        lines.push(format!(
+            "area {}  {:?} {:?}  frequency {:.3}  octaves {}  seed {}",
+            editor.area, area.noise, area.fractal, area.noise_frequency, area.noise_octaves, area.seed
+        ));
+    }
+    lines.push("right mouse paint  tab tool  A agent  Q/E variant or unit".to_string());
+    lines.push("N noise  F fractal  arrows frequency and octaves  G seed".to_string());
+    lines.push(format!("F5 export {}  esc menu", EDITOR_EXPORT_PATH));
+    let next_label = lines.join("\n");
+    if *label == next_label { return; }
+    commands.entity(text).insert(Text::from_section(next_label.clone(), interface_bundle.text_style_secondary.clone()));
+    *label = next_label;
+}
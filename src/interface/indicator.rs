@@ -3,7 +3,7 @@ use crate::common::loader::AssetBundle;
 use crate::common::geometry::assign_mesh_color;
 use crate::logic::{
     MatterBinding,Integrity,UnderConstruction,Suspended,UnitFabrication,
-    MapGrid,NetworkGroupList,GroupLink,MilitaryBinding,
+    MapGrid,NetworkGroupList,GroupLink,MilitaryBinding,ResourceVariant,
 };
 use crate::interaction::GridSelection;
 use crate::scene::InterfaceAssetBundle;
@@ -39,14 +39,22 @@ pub fn update_indicator_display(
     )) = grid.tiles[selection.0].reference.and_then(|entity|query_unit.get(entity).ok()) {
         let Some(group) = group.0.map(|index|&groups[index]) else { return };
 
+        let summary = &group.summary[ResourceVariant::Matter];
+        let mut label = format!("{}/{}+{}", summary.consumption, summary.production, summary.reservation);
+        for resource in ResourceVariant::ALL.into_iter().skip(1) {
+            let summary = &group.summary[resource];
+            if summary.consumption == 0 && summary.production == 0 { continue; }
+            label.push_str(&format!(" {} {}/{}", resource, summary.consumption, summary.production));
+        }
         commands.entity(matter_component.text).insert(Text::from_section(
-            format!("{}/{}+{}", group.summary.matter_consumption, group.summary.matter_production, group.summary.matter_reservation),
-            interface_asset_bundle.text_style_primary.clone()));
+            label, interface_asset_bundle.text_style_primary.clone()));
 
         let material = materials.get_mut(&interface_asset_bundle.matter_material).unwrap();
-        material.fraction = (
-            group.summary.matter_consumption as f32 / (group.summary.matter_production + group.summary.matter_reservation) as f32
-        ).min(1.0);
+        material.fraction = ResourceVariant::ALL.iter().map(|&resource|&group.summary[resource])
+            .filter(|summary|summary.consumption > 0)
+            .map(|summary|summary.consumption as f32 / (summary.production + summary.reservation) as f32)
+            .fold(summary.consumption as f32 / (summary.production + summary.reservation) as f32, f32::max)
+            .min(1.0);
 
         if components.len() <= offset { components.push(IndicatorSingleComponent::new(&mut commands, &layout, &mut meshes, &interface_asset_bundle, offset)); }
 
@@ -63,7 +71,7 @@ pub fn update_indicator_display(
                 matter_component.update_flow_direction(&mut commands, 1);
             },
             (None, None, Some(MatterBinding::Consumption(consumption))) => {
-                components[offset].update_as_value(&mut commands, -consumption.calculated);
+                components[offset].update_as_value(&mut commands, -consumption.calculated.total());
                 matter_component.update_flow_direction(&mut commands, -1);
             },
             (None, None, Some(MatterBinding::Collection(storage))) => {
@@ -85,7 +93,7 @@ pub fn update_indicator_display(
         let construction_percent = construction.map_or(1.0,|construction|construction.calculate(fraction));
         components[offset].update_as_bar(
             &mut commands, &mut meshes,
-            &interface_asset_bundle.icon_shield, integrity.calculate(fraction, construction_percent),integrity.tier(),
+            &interface_asset_bundle.icon_shield, integrity.calculate(fraction, construction_percent),integrity.effective_tier(),
         );
         offset += 1;
 
@@ -98,9 +106,20 @@ pub fn update_indicator_display(
             offset += 1;
         }
 
+        let tile = &grid.tiles[selection.0];
+        if tile.capacity > 0 && matches!(matter, Some(MatterBinding::Production(production)) if production.resource == ResourceVariant::Matter) {
+            if components.len() <= offset { components.push(IndicatorSingleComponent::new(&mut commands, &layout, &mut meshes, &interface_asset_bundle, offset)); }
+            components[offset].update_as_bar(
+                &mut commands, &mut meshes,
+                &interface_asset_bundle.icon_matter, tile.reserve as f32 / tile.capacity as f32, 0,
+            );
+            offset += 1;
+        }
+
         if let Some((value, metric)) = match military {
             Some(MilitaryBinding::Trajectory { cooldown_timer, .. }) => Some((cooldown_timer.percent(), 1)),
             Some(MilitaryBinding::Connection { limit, released, .. }) => Some((*released as f32, *limit)),
+            Some(MilitaryBinding::Barrier { capacity, stored, .. }) => Some((*stored as f32 / *capacity as f32, 1)),
             _ => None
         } {
             if components.len() <= offset { components.push(IndicatorSingleComponent::new(&mut commands, &layout, &mut meshes, &interface_asset_bundle, offset)); }
@@ -110,6 +129,10 @@ pub fn update_indicator_display(
             );
             offset += 1;
         }
+    } else if grid.tiles[selection.0].capacity > 0 {
+        if components.is_empty() { components.push(IndicatorSingleComponent::new(&mut commands, &layout, &mut meshes, &interface_asset_bundle, offset)); }
+        components[offset].update_as_value(&mut commands, grid.tiles[selection.0].reserve);
+        offset += 1;
     }
     for i in offset..components.len() {
         components[i].update_as_empty(&mut commands);
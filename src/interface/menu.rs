@@ -1,11 +1,15 @@
 use bevy::prelude::*;
 use crate::common::loader::AssetBundle;
-use crate::scene::{GlobalState, InterfaceAssetBundle};
+use crate::scene::{GlobalState, InterfaceAssetBundle, BlueprintAssetBundle, StageBlueprint};
+use crate::logic::{Agent, MatchOutcome, ObjectiveOutcome};
 use crate::interaction::{InteractionEvent, EventTrigger};
 
 pub fn update_menu_screen(
     mut commands: Commands,
     interface_bundle: Res<AssetBundle<InterfaceAssetBundle>>,
+    blueprint_bundle: Res<AssetBundle<BlueprintAssetBundle>>,
+    stages: Res<Assets<StageBlueprint>>,
+    outcome: Res<MatchOutcome>,
     state: Res<State<GlobalState>>,
     mut component: Local<Option<Entity>>,
 ){
@@ -28,23 +32,83 @@ pub fn update_menu_screen(
         z_index: ZIndex::Local(16), ..Default::default()
     }).id();
 
-    commands.spawn(ButtonBundle {
-        style: Style {
-            align_items: AlignItems::Center, justify_content: JustifyContent::Center,
-            aspect_ratio: Some(4.0), size: Size::height(Val::Px(40.0)),
+    if let Some(result) = outcome.get(Agent::Player) {
+        commands.spawn(TextBundle {
+            text: Text::from_section(match result {
+                ObjectiveOutcome::Victory => "VICTORY",
+                ObjectiveOutcome::Defeat => "DEFEAT",
+            }, interface_bundle.text_style_primary.clone()),
             ..Default::default()
-        },
-        background_color: interface_bundle.color_enabled.clone().into(),
-        image: interface_bundle.panel_extended.clone().into(),
-        ..Default::default()
-    })
-    .insert(EventTrigger(InteractionEvent::Start(0)))
-    .with_children(|parent|{
-        parent.spawn(TextBundle {
-            text: Text::from_section("PLAY", interface_bundle.text_style_primary.clone()),
+        }).set_parent(entity);
+        for (agent, result) in outcome.agents.iter() {
+            let &Agent::AI(agent_index) = agent else { continue };
+            let label = match result {
+                Some(ObjectiveOutcome::Victory) => "victory",
+                Some(ObjectiveOutcome::Defeat) => "defeat",
+                None => "undecided",
+            };
+            commands.spawn(TextBundle {
+                text: Text::from_section(format!("AI {}: {}", agent_index, label), interface_bundle.text_style_secondary.clone()),
+                ..Default::default()
+            }).set_parent(entity);
+        }
+        commands.spawn(NodeBundle {
+            style: Style { size: Size::height(Val::Px(32.0)), ..Default::default() },
             ..Default::default()
-        });
-    }).set_parent(entity);
+        }).set_parent(entity);
+    }
+
+    for (index, handle) in blueprint_bundle.stages.iter().enumerate() {
+        let Some(stage) = stages.get(handle) else { continue };
+        let row = commands.spawn(NodeBundle {
+            style: Style { flex_direction: FlexDirection::Row, align_items: AlignItems::Center, ..Default::default() },
+            ..Default::default()
+        }).set_parent(entity).id();
+        commands.spawn(ButtonBundle {
+            style: Style {
+                align_items: AlignItems::Center, justify_content: JustifyContent::Center,
+                aspect_ratio: Some(4.0), size: Size::height(Val::Px(40.0)),
+                ..Default::default()
+            },
+            background_color: interface_bundle.color_enabled.clone().into(),
+            image: interface_bundle.panel_extended.clone().into(),
+            ..Default::default()
+        })
+        .insert(EventTrigger(InteractionEvent::Start(index)))
+        .with_children(|parent|{
+            parent.spawn(TextBundle {
+                text: Text::from_section(stage.name.to_uppercase(), interface_bundle.text_style_primary.clone()),
+                ..Default::default()
+            });
+        }).set_parent(row);
+        commands.spawn(ButtonBundle {
+            style: Style {
+                align_items: AlignItems::Center, justify_content: JustifyContent::Center,
+                aspect_ratio: Some(2.0), size: Size::height(Val::Px(32.0)),
+                margin: UiRect::left(Val::Px(8.0)),
+                ..Default::default()
+            },
+            background_color: interface_bundle.color_enabled.clone().into(),
+            image: interface_bundle.panel_extended.clone().into(),
+            ..Default::default()
+        })
+        .insert(EventTrigger(InteractionEvent::Edit(index)))
+        .with_children(|parent|{
+            parent.spawn(TextBundle {
+                text: Text::from_section("EDIT", interface_bundle.text_style_secondary.clone()),
+                ..Default::default()
+            });
+        }).set_parent(row);
+
+        commands.spawn(TextBundle {
+            text: Text::from_section(stage.description.clone(), interface_bundle.text_style_secondary.clone()),
+            style: Style {
+                margin: UiRect::bottom(Val::Px(16.0)),
+                ..Default::default()
+            },
+            ..Default::default()
+        }).set_parent(entity);
+    }
 
     commands.spawn(ButtonBundle {
         style: Style {
@@ -0,0 +1,46 @@
+use bevy::prelude::*;
+use crate::common::loader::AssetBundle;
+use crate::interaction::ViewMode;
+use crate::logic::StageEvent;
+use crate::scene::InterfaceAssetBundle;
+
+const MESSAGE_DURATION: f32 = 8.0;
+
+pub fn update_message_display(
+    mut commands: Commands,
+    time: Res<Time>,
+    mode: Res<ViewMode>,
+    interface_bundle: Res<AssetBundle<InterfaceAssetBundle>>,
+    mut events: EventReader<StageEvent>,
+    mut component: Local<Option<(Entity, f32)>>,
+){
+    let elapsed = time.raw_elapsed_seconds();
+    let mut next_message: Option<String> = None;
+    for event in events.iter() {
+        let StageEvent::Message(text) = event;
+        next_message = Some(text.clone());
+    }
+    let expired = component.map_or(false, |(_, expiration)|expiration <= elapsed);
+    if next_message.is_none() && !expired && !matches!(mode.as_ref(), ViewMode::Menu) { return; }
+    if let Some((entity, _)) = component.take() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(text) = next_message else { return };
+
+    let entity = commands.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute, size: Size::width(Val::Percent(100.0)),
+            position: UiRect::top(Val::Px(48.0)),
+            justify_content: JustifyContent::Center,
+            ..Default::default()
+        },
+        focus_policy: bevy::ui::FocusPolicy::Pass,
+        z_index: ZIndex::Local(8), ..Default::default()
+    }).with_children(|parent|{
+        parent.spawn(TextBundle {
+            text: Text::from_section(text, interface_bundle.text_style_primary.clone()),
+            ..Default::default()
+        });
+    }).id();
+    component.replace((entity, elapsed + MESSAGE_DURATION));
+}
@@ -7,8 +7,13 @@ pub mod menu;
 pub mod construct;
 pub mod deconstruct;
 pub mod toggle;
+pub mod queue;
+pub mod priority;
+pub mod speed;
 pub mod control;
 pub mod indicator;
+pub mod message;
+pub mod editor;
 
 use bevy::prelude::*;
 use crate::common::loader::LoadingState;
@@ -27,6 +32,9 @@ pub struct InterfacePlugin; impl Plugin for InterfacePlugin {
         app.add_systems((
             deconstruct::update_unit_controls_deconstruct,
             toggle::update_unit_controls_toggle,
+            queue::update_unit_controls_queue,
+            priority::update_unit_controls_priority,
+            speed::update_speed_display,
             construct::update_construction_menu,
             control::update_unit_controls_action,
             control::update_unit_subcontrols_action,
@@ -35,5 +43,7 @@ pub struct InterfacePlugin; impl Plugin for InterfacePlugin {
         .in_set(OnUpdate(LoadingState::Running)));
 
         app.add_system(menu::update_menu_screen.in_set(OnUpdate(LoadingState::Running)));
+        app.add_system(message::update_message_display.in_set(OnUpdate(LoadingState::Running)));
+        app.add_system(editor::update_editor_display.in_set(OnUpdate(LoadingState::Running)));
     }
 }
\ No newline at end of file
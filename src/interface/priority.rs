@@ -0,0 +1,48 @@
+use bevy::prelude::*;
+use crate::common::loader::AssetBundle;
+use crate::interaction::{ViewMode, GridSelection, InteractionEvent, SelectionState};
+use crate::logic::{Agent, GroupLink, PriorityOrder, PriorityLevel};
+use crate::scene::InterfaceAssetBundle;
+use super::layout::OverlayLayout;
+use super::shared::{ControlComponent, ControlComponentDescriptor};
+
+pub fn update_unit_controls_priority(
+    mut commands: Commands,
+    mut component: Local<Option<ControlComponent>>,
+    mode: Res<ViewMode>,
+    layout: Res<OverlayLayout>,
+    interface_bundle: Res<AssetBundle<InterfaceAssetBundle>>,
+    query_unit: Query<(Entity, &Agent, Option<&PriorityLevel>), (With<GridSelection>, With<GroupLink>, With<PriorityOrder>)>,
+){
+    let component = component.get_or_insert_with(||ControlComponent::new(
+        &mut commands, &layout, ControlComponentDescriptor {
+            quadrant: 3, size: 16.0, angle: std::f32::consts::PI * 9.0 / 8.0,
+            image_panel: interface_bundle.panel_single.clone(),
+            image_icon: interface_bundle.icon_matter.clone(),
+            color_enabled: interface_bundle.color_enabled,
+            color_active: Some(interface_bundle.color_active),
+            text_style: Some(interface_bundle.text_style_secondary.clone()),
+            ..Default::default()
+        }
+    ));
+    if let ViewMode::Default(global_agent) = mode.as_ref() {
+        if let Ok((entity, agent, level)) = query_unit.get_single() {
+            if global_agent == agent {
+                let level = level.copied().unwrap_or_default();
+                component.set_state(&mut commands, match level {
+                    PriorityLevel::Critical => SelectionState::Active,
+                    _ => SelectionState::Enabled,
+                });
+                component.set_label(&mut commands, match level {
+                    PriorityLevel::Critical => "critical",
+                    PriorityLevel::Normal => "normal",
+                    PriorityLevel::Low => "low",
+                }.to_string());
+                component.set_trigger(&mut commands, InteractionEvent::Prioritize(entity, level.next()));
+                return;
+            }
+        }
+    }
+    component.clear_trigger(&mut commands);
+    component.set_state(&mut commands, SelectionState::Disabled);
+}
@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+use crate::common::loader::AssetBundle;
+use crate::interaction::{ViewMode, GridSelection, InteractionEvent, SelectionState};
+use crate::logic::{Agent, GroupLink, NetworkGroupList, QueuedConstruction};
+use crate::scene::InterfaceAssetBundle;
+use super::layout::OverlayLayout;
+use super::shared::{ControlComponent, ControlComponentDescriptor};
+
+pub fn update_unit_controls_queue(
+    mut commands: Commands,
+    mut components: Local<Vec<ControlComponent>>,
+    mode: Res<ViewMode>,
+    layout: Res<OverlayLayout>,
+    interface_bundle: Res<AssetBundle<InterfaceAssetBundle>>,
+    query_grid: Query<&NetworkGroupList>,
+    query_unit: Query<(Entity, &Parent, &Agent, &GroupLink), (With<GridSelection>, With<QueuedConstruction>)>,
+){
+    if components.is_empty() {
+        for offset in 0..2 {
+            components.push(ControlComponent::new(&mut commands, &layout, ControlComponentDescriptor {
+                quadrant: 1, size: 16.0, angle: -((offset as f32 + 0.5) / 5.0).asin(),
+                image_panel: interface_bundle.panel_single.clone(),
+                image_icon: interface_bundle.icon_build.clone(),
+                color_enabled: interface_bundle.color_enabled,
+                text_style: Some(interface_bundle.text_style_secondary.clone()),
+                ..Default::default()
+            }));
+        }
+    }
+    let mut enabled = [false; 2];
+    if let ViewMode::Default(global_agent) = mode.as_ref() {
+        if let Some((entity, queue)) = query_unit.get_single().ok()
+        .filter(|(_, _, agent, _)|*agent == global_agent)
+        .and_then(|(entity, parent, _, link)|Some((entity, &query_grid.get(parent.get()).ok()?.get((**link)?)?.queue))) {
+            let position = queue.iter().position(|&queued|queued == entity).unwrap_or_default();
+            enabled = [position > 0, position + 1 < queue.len()];
+            for (index, (component, label)) in components.iter_mut().zip(["ahead", "behind"]).enumerate() {
+                if !enabled[index] { continue; }
+                component.set_state(&mut commands, SelectionState::Enabled);
+                component.set_label(&mut commands, format!("{} {}/{}", label, position + 1, queue.len()));
+                component.set_trigger(&mut commands, InteractionEvent::Reorder(entity, index == 0));
+            }
+        }
+    }
+    for (component, enabled) in components.iter_mut().zip(enabled) {
+        if enabled { continue; }
+        component.clear_trigger(&mut commands);
+        component.set_state(&mut commands, SelectionState::Disabled);
+    }
+}
@@ -0,0 +1,39 @@
+use bevy::prelude::*;
+use crate::common::loader::AssetBundle;
+use crate::interaction::{ViewMode, SelectionState};
+use crate::logic::SimulationSpeed;
+use crate::scene::InterfaceAssetBundle;
+use super::layout::OverlayLayout;
+use super::shared::{ControlComponent, ControlComponentDescriptor};
+
+pub fn update_speed_display(
+    mut commands: Commands,
+    mut component: Local<Option<ControlComponent>>,
+    mode: Res<ViewMode>,
+    layout: Res<OverlayLayout>,
+    interface_bundle: Res<AssetBundle<InterfaceAssetBundle>>,
+    speed: Res<SimulationSpeed>,
+){
+    let component = component.get_or_insert_with(||ControlComponent::new(
+        &mut commands, &layout, ControlComponentDescriptor {
+            quadrant: 3, size: 12.0, angle: std::f32::consts::PI * 11.0 / 8.0,
+            image_panel: interface_bundle.panel_single.clone(),
+            image_icon: interface_bundle.icon_rate.clone(),
+            color_enabled: interface_bundle.color_enabled,
+            color_active: Some(interface_bundle.color_disabled),
+            text_style: Some(interface_bundle.text_style_secondary.clone()),
+            ..Default::default()
+        }
+    ));
+    if let ViewMode::Menu | ViewMode::Editor = mode.as_ref() {
+        component.set_state(&mut commands, SelectionState::Disabled);
+        return;
+    }
+    if !speed.is_changed() && component.state != SelectionState::Disabled { return; }
+    component.set_state(&mut commands, if speed.paused { SelectionState::Active }else{ SelectionState::Enabled });
+    component.set_label(&mut commands, if speed.paused {
+        "paused".to_string()
+    } else {
+        format!("x{}", speed.multiplier())
+    });
+}
@@ -1,7 +1,58 @@
 use bevy::prelude::*;
 
-#[derive(Component, serde::Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+#[derive(Component, serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
 pub enum Agent {
     #[default] Player,
     AI(u8)
-}
\ No newline at end of file
+}
+impl Agent {
+    ///Highest AI index with a bit of its own in visibility masks.
+    pub const MAX_AI_INDEX: u8 = 30;
+    ///Bit assigned to the agent in visibility masks.
+    pub fn mask(&self) -> u32 { match self {
+        Agent::Player => 1,
+        Agent::AI(index) => 1 << ((*index).min(Self::MAX_AI_INDEX) as u32 + 1),
+    } }
+    pub fn is_valid(&self) -> bool { match self {
+        Agent::Player => true,
+        Agent::AI(index) => *index <= Self::MAX_AI_INDEX,
+    } }
+}
+///Teams of allied agents, any agent not listed forms a team of its own.
+///Neutral agents never pick targets, but can still be attacked.
+#[derive(Resource, serde::Serialize, serde::Deserialize, Clone, Default, Debug)]
+pub struct AllianceTable {
+    #[serde(default)] pub teams: Vec<Vec<Agent>>,
+    #[serde(default)] pub neutral: Vec<Agent>,
+}
+impl AllianceTable {
+    pub fn is_allied(&self, agent: &Agent, other: &Agent) -> bool {
+        agent == other || self.teams.iter().any(|team|team.contains(agent) && team.contains(other))
+    }
+    pub fn is_neutral(&self, agent: &Agent) -> bool { self.neutral.contains(agent) }
+    ///Agents share vision with their allies.
+    pub fn vision_mask(&self, agent: &Agent) -> u32 {
+        self.teams.iter().filter(|team|team.contains(agent)).flatten()
+        .fold(agent.mask(), |mask, other|mask | other.mask())
+    }
+    pub fn is_hostile(&self, agent: &Agent, target: &Agent) -> bool {
+        !self.is_neutral(agent) && !self.is_allied(agent, target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn agent_masks_are_distinct(){
+        let agents: Vec<Agent> = std::iter::once(Agent::Player)
+            .chain((0..=Agent::MAX_AI_INDEX).map(Agent::AI)).collect();
+        let combined = agents.iter().fold(0u32, |mask, agent|{
+            assert_eq!(mask & agent.mask(), 0);
+            mask | agent.mask()
+        });
+        assert_eq!(combined, u32::MAX);
+        assert!(!Agent::AI(Agent::MAX_AI_INDEX + 1).is_valid());
+    }
+}
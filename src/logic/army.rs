@@ -0,0 +1,142 @@
+use bevy::prelude::*;
+use crate::common::adjacency::{dijkstra_distance_field, DistanceField};
+use crate::effects::animation::MovementFormation;
+use crate::interaction::{InteractionEvent, ActionSelector, path::ActionPath};
+use crate::scene::UnitBlueprint;
+use crate::logic::{Agent, AllianceTable, GridTileIndex, MapGrid, GroupLink, NetworkGroupList, SimulationTick};
+use crate::logic::{TerrainVariantTable, GridVisibility, SpatialLookupGrid, StrategySettings, hostile_distance_field};
+use crate::logic::{Integrity, UnderConstruction, FollowingPath, MilitarySupply, UnitDirective};
+
+///Mobile unit held back by the AI until an attack wave or a threat, ignored by automatic target pursuit.
+#[derive(Component, Clone, Default)]
+pub struct ArmyReserve;
+
+pub fn military_planning_phase(
+    tick: Res<SimulationTick>,
+    settings: Res<StrategySettings>,
+    alliances: Res<AllianceTable>,
+    terrain: Res<TerrainVariantTable>,
+    lookup: Res<SpatialLookupGrid<Entity>>,
+    mut commands: Commands,
+    mut events: EventWriter<InteractionEvent>,
+    blueprints: Res<Assets<UnitBlueprint>>,
+    query_grid: Query<(Entity, &MapGrid, &NetworkGroupList, Option<&GridVisibility>)>,
+    query_structure: Query<(&Handle<UnitBlueprint>, &GlobalTransform), (With<GroupLink>, Without<UnderConstruction>)>,
+    query_army: Query<(
+        Entity, &Parent, &Agent, &GridTileIndex, &Integrity, Option<&FollowingPath>, Option<&ArmyReserve>
+    ), (With<MovementFormation>, With<MilitarySupply>)>,
+    query_hostile: Query<(&Parent, &Agent, &GridTileIndex), With<Integrity>>,
+){
+    for (parent, grid, groups, visibility) in query_grid.iter() {
+        let mut agents: Vec<Agent> = Vec::new();
+        for group in groups.iter() {
+            if !agents.contains(&group.agent) { agents.push(group.agent); }
+        }
+        for agent in agents.into_iter() {
+            let Some(profile) = settings.profile(&agent) else { continue };
+            if tick.elapsed % profile.interval.max(1) != 0 { continue; }
+            let mask = alliances.vision_mask(&agent);
+            let passable = |index: usize|grid.tiles[index].is_empty() || grid.tiles[index].reference.is_some();
+
+            let structures: Vec<(usize, Entity)> = groups.iter()
+            .filter(|group|group.agent == agent)
+            .flat_map(|group|group.list.iter().copied()).collect();
+
+            let mut threats: Vec<usize> = Vec::new();
+            for &(_, entity) in structures.iter() {
+                let Ok((_, transform)) = query_structure.get(entity) else { continue };
+                for &(target, _) in lookup.query_around(transform.translation(), profile.threat_radius) {
+                    let Ok((target_parent, target_agent, tile_index)) = query_hostile.get(target) else { continue };
+                    if target_parent.get() != parent || !alliances.is_hostile(&agent, target_agent) { continue; }
+                    if !visibility.map_or(false, |visibility|visibility.is_visible(tile_index.0, mask)) { continue; }
+                    threats.push(tile_index.0);
+                }
+            }
+            threats.sort_unstable();
+            threats.dedup();
+
+            let mut home_field: Option<DistanceField<usize>> = None;
+            let mut reserve: Vec<(Entity, usize, bool)> = Vec::new();
+            for (
+                entity, unit_parent, unit_agent, tile_index, integrity, movement, held
+            ) in query_army.iter() {
+                if unit_parent.get() != parent || *unit_agent != agent || movement.is_some() { continue; }
+                let damaged = integrity.calculate(1.0, 1.0) < profile.retreat_threshold;
+                if held.is_some() {
+                    reserve.push((entity, tile_index.0, damaged));
+                } else if damaged {
+                    let field = home_field.get_or_insert_with(||dijkstra_distance_field(
+                        &grid.graph, structures.iter().map(|&(index, _)|index),
+                        |prev, index|if passable(index) { Some(grid.movement_cost(&terrain, prev, index)) }else{ None },
+                        None
+                    ));
+                    let Some(nodes) = field.path_from(tile_index.0).filter(|nodes|nodes.len() > 1) else { continue };
+                    commands.entity(entity).insert(FollowingPath::from(nodes)).insert(ArmyReserve);
+                }
+            }
+
+            if !threats.is_empty() {
+                let field = dijkstra_distance_field(
+                    &grid.graph, threats.iter().copied(),
+                    |prev, index|if passable(index) { Some(grid.movement_cost(&terrain, prev, index)) }else{ None },
+                    None
+                );
+                for &(entity, index, _) in reserve.iter().filter(|unit|!unit.2) {
+                    commands.entity(entity).remove::<ArmyReserve>();
+                    let Some(nodes) = field.path_from(index).filter(|nodes|nodes.len() > 1) else { continue };
+                    commands.entity(entity).insert(FollowingPath::from(nodes));
+                }
+            } else if reserve.iter().filter(|unit|!unit.2).count() >= profile.wave_size.max(1) {
+                let field = hostile_distance_field(&alliances, &terrain, &agent, grid, visibility);
+                for &(entity, index, _) in reserve.iter().filter(|unit|!unit.2) {
+                    let Some(nodes) = field.path_from(index).filter(|nodes|nodes.len() > 1) else { continue };
+                    commands.entity(entity).remove::<ArmyReserve>().insert(FollowingPath::from(nodes));
+                }
+            }
+
+            if threats.is_empty() { continue; }
+            let front = dijkstra_distance_field(
+                &grid.graph, threats.iter().copied(),
+                |prev, index|if grid.tiles[index].is_empty() { Some(grid.movement_cost(&terrain, prev, index)) }else{ None },
+                None
+            );
+            for &(index, entity) in structures.iter() {
+                let Ok((handle, transform)) = query_structure.get(entity) else { continue };
+                let Some(blueprint) = blueprints.get(handle) else { continue };
+                if blueprint.action != Some(UnitDirective::Relocate) { continue; }
+                let Some(military) = blueprint.military.as_ref() else { continue };
+                if lookup.query_around(transform.translation(), military.radius())
+                .any(|&(target, _)|query_hostile.get(target).map_or(false, |(target_parent, target_agent, _)|
+                    target_parent.get() == parent && alliances.is_hostile(&agent, target_agent)
+                )) { continue; }
+
+                let Some(mut nodes) = grid.graph.neighbors(index).into_iter().flatten()
+                .filter_map(|&adjacent|front.distance(adjacent).map(|distance|(adjacent, distance)))
+                .min_by(|a, b|a.1.total_cmp(&b.1))
+                .and_then(|(adjacent, _)|front.path_from(adjacent)) else { continue };
+                nodes.pop();
+                nodes.insert(0, index);
+                nodes.truncate(profile.advance_distance + 1);
+                if nodes.len() < 2 { continue; }
+                events.send(InteractionEvent::Execute(entity, ActionSelector::FollowPath(Some(ActionPath{ nodes })), 0));
+            }
+        }
+    }
+}
+
+///Idle reserve units next to structures of their agent recover integrity, one point per tick.
+pub fn restore_reserve_phase(
+    query_grid: Query<&MapGrid>,
+    query_structure: Query<&Agent, With<GroupLink>>,
+    mut query_unit: Query<(&Parent, &Agent, &GridTileIndex, &mut Integrity), (With<ArmyReserve>, Without<FollowingPath>)>,
+){
+    for (parent, agent, tile_index, mut integrity) in query_unit.iter_mut() {
+        if integrity.absorbed <= 0 { continue; }
+        let Ok(grid) = query_grid.get(parent.get()) else { continue };
+        if !std::iter::once(tile_index.0)
+            .chain(grid.graph.neighbors(tile_index.0).into_iter().flatten().copied())
+            .filter_map(|index|grid.tiles[index].reference)
+            .any(|entity|query_structure.get(entity).map_or(false, |owner|owner == agent)) { continue; }
+        integrity.absorbed -= 1;
+    }
+}
@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+use crate::common::noise::MurMurHash;
+
+#[derive(Resource, serde::Serialize, serde::Deserialize, Clone, Copy, Default, Debug)]
+pub struct SimulationTick {
+    pub elapsed: u64,
+    pub seed: u64,
+}
+impl SimulationTick {
+    pub fn random(&self) -> MurMurHash {
+        let mut rng = MurMurHash::from_seed(self.seed);
+        rng.next(self.elapsed);
+        rng
+    }
+    pub fn on_interval(interval: u64) -> impl FnMut(Res<SimulationTick>) -> bool + Clone {
+        move |tick: Res<SimulationTick>|tick.elapsed % interval == 0
+    }
+}
+
+///Relative rate of the simulation clock, applied to the virtual time driving fixed updates and effects.
+#[derive(Resource, Clone, Copy, PartialEq, Debug)]
+pub struct SimulationSpeed {
+    pub level: usize,
+    pub paused: bool,
+    ///Single ticks requested while paused.
+    pub steps: u32,
+}
+impl Default for SimulationSpeed {
+    fn default() -> Self { Self { level: 1, paused: false, steps: 0 } }
+}
+impl SimulationSpeed {
+    pub const LEVELS: [f32; 4] = [0.25, 1.0, 2.0, 4.0];
+    pub fn multiplier(&self) -> f32 { Self::LEVELS[self.level.min(Self::LEVELS.len() - 1)] }
+}
+
+pub fn apply_simulation_speed(
+    mut speed: ResMut<SimulationSpeed>,
+    mut time: ResMut<Time>,
+    mut fixed_time: ResMut<FixedTime>,
+){
+    if speed.is_changed() {
+        time.set_relative_speed(speed.multiplier());
+        if speed.paused { time.pause(); }else{ time.unpause(); }
+    }
+    if speed.paused && speed.steps > 0 {
+        speed.bypass_change_detection().steps -= 1;
+        let period = fixed_time.period;
+        fixed_time.tick(period);
+    }
+}
+
+pub fn reset_simulation_speed(mut speed: ResMut<SimulationSpeed>){
+    *speed = SimulationSpeed::default();
+}
+
+pub fn reset_simulation_tick(time: Res<Time>, mut tick: ResMut<SimulationTick>){
+    tick.elapsed = 0;
+    tick.seed = time.elapsed().as_nanos() as u64;
+}
+
+pub fn advance_simulation_tick(mut tick: ResMut<SimulationTick>){
+    tick.elapsed += 1;
+}
@@ -14,6 +14,7 @@ pub enum UpgradeVariant {
     Amplitude(UpgradeAmplitude),
     Frequency(UpgradeFrequency),
     Range(UpgradeRange),
+    Regenerate(UpgradeRegenerate),
     Immobilize(DegradeImmobilize),
 }
 impl UpgradeVariant {
@@ -22,6 +23,7 @@ impl UpgradeVariant {
             UpgradeVariant::Amplitude(component) => { commands.insert_add(component.clone()); },
             UpgradeVariant::Frequency(component) => { commands.insert_add(component.clone()); },
             UpgradeVariant::Range(component) => { commands.insert_add(component.clone()); },
+            UpgradeVariant::Regenerate(component) => { commands.insert_add(component.clone()); },
             UpgradeVariant::Immobilize(component) => { commands.insert_add(component.clone()); },
         }
     }
@@ -45,6 +47,13 @@ impl AddAssign<UpgradeAmplitude> for UpgradeAmplitude {
     #[inline] fn add_assign(&mut self, rhs: UpgradeAmplitude) { self.0 += rhs.0; }
 }
 
+///Additional integrity restored per cycle.
+#[derive(Component, serde::Deserialize, Deref, DerefMut, Clone, Copy, Default, Debug)]
+pub struct UpgradeRegenerate(pub i32);
+impl AddAssign<UpgradeRegenerate> for UpgradeRegenerate {
+    #[inline] fn add_assign(&mut self, rhs: UpgradeRegenerate) { self.0 += rhs.0; }
+}
+
 #[derive(Component, serde::Deserialize, Deref, DerefMut, Clone, Copy, Default, Debug)]
 pub struct DegradeImmobilize(pub i32);
 impl AddAssign<DegradeImmobilize> for DegradeImmobilize {
@@ -59,13 +68,13 @@ pub fn expiration_phase(
     mut commands: Commands,
     query: Query<Entity, Or<(
         With<UpgradeAmplitude>, With<UpgradeFrequency>, With<UpgradeRange>,
-        With<DegradeImmobilize>,
+        With<UpgradeRegenerate>, With<DegradeImmobilize>,
     )>>,
 ){
     for entity in query.iter() {
         commands.entity(entity).remove::<(
             UpgradeAmplitude,UpgradeFrequency,UpgradeRange,
-            DegradeImmobilize
+            UpgradeRegenerate,DegradeImmobilize
         )>();
     }
 }
@@ -1,8 +1,8 @@
 use bevy::prelude::*;
-use std::ops::AddAssign;
+use std::ops::{AddAssign, Index, IndexMut};
 use crate::common::animation::ease::lerp;
 
-#[derive(Resource, serde::Deserialize, Clone, Default)]
+#[derive(Resource, serde::Serialize, serde::Deserialize, Clone, Default)]
 pub struct GlobalEconomy {
     pub density: Vec<i32>,
     #[serde(default, skip)] pub priority: u64,
@@ -11,15 +11,62 @@ impl GlobalEconomy {
     pub fn next_priority(&mut self) -> u64 { self.priority += 1; self.priority }
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum ResourceVariant {
+    #[default] Matter,
+    ///Generated and consumed within the same cycle, surplus is discarded.
+    Energy,
+}
+impl ResourceVariant {
+    pub const COUNT: usize = 2;
+    pub const ALL: [ResourceVariant; Self::COUNT] = [ResourceVariant::Matter, ResourceVariant::Energy];
+    pub fn storable(&self) -> bool { match self {
+        ResourceVariant::Matter => true,
+        ResourceVariant::Energy => false,
+    } }
+}
+impl std::fmt::Display for ResourceVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { f.write_str(match self {
+        ResourceVariant::Matter => "Matter",
+        ResourceVariant::Energy => "Energy",
+    }) }
+}
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ResourceAmount(pub [i32; ResourceVariant::COUNT]);
+impl Index<ResourceVariant> for ResourceAmount {
+    type Output = i32;
+    fn index(&self, resource: ResourceVariant) -> &i32 { &self.0[resource as usize] }
+}
+impl IndexMut<ResourceVariant> for ResourceAmount {
+    fn index_mut(&mut self, resource: ResourceVariant) -> &mut i32 { &mut self.0[resource as usize] }
+}
+impl ResourceAmount {
+    pub fn total(&self) -> i32 { self.0.iter().sum() }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ResourceSummary {
+    pub amount: i32,
+    pub reservation: i32,
+    pub consumption: i32,
+    pub production: i32,
+}
+impl ResourceSummary {
+    pub fn delta(&self) -> i32 { self.production - self.consumption }
+}
+
 #[derive(Clone, Default)]
-pub struct EconomySummary {
-    pub matter: i32,
-    pub matter_reservation: i32,
-    pub matter_consumption: i32,
-    pub matter_production: i32,
+pub struct EconomySummary(pub [ResourceSummary; ResourceVariant::COUNT]);
+impl Index<ResourceVariant> for EconomySummary {
+    type Output = ResourceSummary;
+    fn index(&self, resource: ResourceVariant) -> &ResourceSummary { &self.0[resource as usize] }
+}
+impl IndexMut<ResourceVariant> for EconomySummary {
+    fn index_mut(&mut self, resource: ResourceVariant) -> &mut ResourceSummary { &mut self.0[resource as usize] }
 }
 
-#[derive(Component, serde::Deserialize, Clone, Debug)]
+#[derive(Component, serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub enum MatterBinding {
     Production(MatterProduction),
     Consumption(MatterConsumption),
@@ -40,29 +87,41 @@ impl AddAssign<MatterBinding> for MatterBinding {
     } }
 }
 
-#[derive(serde::Deserialize, Clone, Default, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default, Debug)]
 pub struct MatterProduction {
     pub efficiency: i32,
+    #[serde(default)] pub resource: ResourceVariant,
     #[serde(default, skip)] pub extracted: i32,
 }
 
-#[derive(serde::Deserialize, Clone, Default, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default, Debug)]
 pub struct MatterConsumption {
     pub quota: i32,
-    #[serde(default, skip)] pub calculated: i32,
-    #[serde(default, skip)] pub transfered: i32,
+    #[serde(default)] pub resource: ResourceVariant,
+    ///Additional resources required alongside the primary quota.
+    #[serde(default)] pub inputs: Vec<(ResourceVariant, i32)>,
+    #[serde(default, skip)] pub calculated: ResourceAmount,
+    #[serde(default, skip)] pub transfered: ResourceAmount,
+    #[serde(default, skip)] pub starving: bool,
 }
 impl MatterConsumption {
-    pub fn active(&self) -> bool { self.calculated >= self.quota && self.transfered >= self.calculated }
+    pub fn requirements(&self) -> impl Iterator<Item = (ResourceVariant, i32)> + '_ {
+        std::iter::once((self.resource, self.quota)).chain(self.inputs.iter().copied())
+    }
+    pub fn active(&self) -> bool {
+        self.requirements().all(|(resource, quota)|self.calculated[resource] >= quota) &&
+        ResourceVariant::ALL.iter().all(|&resource|self.transfered[resource] >= self.calculated[resource])
+    }
 }
 
-#[derive(serde::Deserialize, Clone, Default, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default, Debug)]
 pub struct MatterStorage {
     pub key: String,
     pub capacity: i32,
     pub stored: i32,
     pub recharge: i32,
     pub discharge: i32,
+    #[serde(default)] pub resource: ResourceVariant,
     #[serde(default, skip)] pub reserved: i32,
     #[serde(default, skip)] pub prev_stored: i32,
 }
@@ -74,8 +133,8 @@ impl MatterStorage {
     pub fn delta(&self) -> i32 { self.stored - self.prev_stored }
 }
 
-use crate::logic::{MapGrid, GridTileIndex, GroupLink, NetworkGroupList};
-use crate::logic::{Integrity, Suspended, UnderConstruction, UpgradeAmplitude, UpgradeFrequency};
+use crate::logic::{Agent, MapGrid, GridTileIndex, GroupLink, NetworkGroupList, EconomyEvent, StageEvent, StrategySettings};
+use crate::logic::{Integrity, Suspended, UnderConstruction, QueuedConstruction, UpgradeAmplitude, UpgradeFrequency};
 
 pub fn reset_economy_phase(
     mut query_grid: Query<&mut NetworkGroupList>,
@@ -83,10 +142,7 @@ pub fn reset_economy_phase(
 ){
     for mut groups in query_grid.iter_mut() {
         for group in groups.iter_mut() {
-            group.summary.matter = 0;
-            group.summary.matter_production = 0;
-            group.summary.matter_consumption = 0;
-            group.summary.matter_reservation = 0;
+            group.summary = EconomySummary::default();
         }
     }
     for (mut matter, integrity) in query_unit.iter_mut() {
@@ -95,8 +151,8 @@ pub fn reset_economy_phase(
                 production.extracted = 0;
             },
             MatterBinding::Consumption(consumption) => {
-                consumption.calculated = 0;
-                consumption.transfered = 0;
+                consumption.calculated = ResourceAmount::default();
+                consumption.transfered = ResourceAmount::default();
             },
             MatterBinding::Collection(storage) => {
                 storage.reserved = 0;
@@ -109,7 +165,8 @@ pub fn reset_economy_phase(
 
 pub fn production_phase(
     economy: Res<GlobalEconomy>,
-    mut query_grid: Query<(&MapGrid, &mut NetworkGroupList)>,
+    settings: Res<StrategySettings>,
+    mut query_grid: Query<(&mut MapGrid, &mut NetworkGroupList)>,
     mut query_unit: Query<(
         &Parent, &GridTileIndex, &GroupLink, &mut MatterBinding,
         Option<&UpgradeFrequency>, Option<&UpgradeAmplitude>,
@@ -120,16 +177,32 @@ pub fn production_phase(
         mut matter, frequency, amplitude,
     ) in query_unit.iter_mut() {
         let MatterBinding::Production(production) = matter.as_mut() else { continue };
-        let Ok((grid, mut groups)) = query_grid.get_mut(parent.get()) else { continue };
+        let Ok((mut grid, mut groups)) = query_grid.get_mut(parent.get()) else { continue };
         let Some(group) = group.map(|i|&mut groups[i]) else { continue };
 
-        let tile = &grid.tiles[**tile_index];
-        let density: i32 = economy.density[tile.variant];
+        let density: i32 = match production.resource {
+            ResourceVariant::Matter => economy.density[grid.tiles[**tile_index].variant],
+            ResourceVariant::Energy => 1,
+        };
         let frequency = frequency.map_or(0,|upgrade|upgrade.0);
         let amplitude = amplitude.map_or(0,|upgrade|upgrade.0);
         production.extracted = (density + amplitude) * (production.efficiency + frequency);
+        //reserves do not affect the network layout, draining them must not trigger relinking
+        if production.resource == ResourceVariant::Matter && grid.tiles[**tile_index].capacity > 0 {
+            let tile = &grid.tiles[**tile_index];
+            production.extracted = ((production.extracted as f32 * tile.deposit()).ceil() as i32).min(tile.reserve);
+            if production.extracted > 0 {
+                grid.bypass_change_detection().tiles[**tile_index].reserve -= production.extracted;
+            }
+        }
 
-        group.summary.matter_production += production.extracted;
+        group.summary[production.resource].production += production.extracted;
+    }
+    for (_, mut groups) in query_grid.iter_mut() {
+        for group in groups.iter_mut() {
+            let Some(profile) = settings.profile(&group.agent) else { continue };
+            group.summary[ResourceVariant::Matter].production += profile.income;
+        }
     }
 }
 
@@ -141,54 +214,106 @@ pub fn reservation_phase(
 ){
     for (parent, group, mut matter, amplitude) in query_unit.iter_mut() {
         let MatterBinding::Collection(storage) = matter.as_mut() else { continue };
+        if !storage.resource.storable() { continue; }
         let Ok(mut groups) = query_grid.get_mut(parent.get()) else { continue };
         let Some(group) = group.map(|i|&mut groups[i]) else { continue };
 
         let amplitude = 1 + amplitude.map_or(0,|upgrade|upgrade.0);
         storage.reserved = storage.discharge.min(storage.stored);
-        group.summary.matter_reservation += amplitude * storage.reserved;
+        group.summary[storage.resource].reservation += amplitude * storage.reserved;
     }
 }
 
 pub fn resource_allocation_phase(
+    mut commands: Commands,
+    mut events: EventWriter<EconomyEvent>,
     mut query_grid: Query<&mut NetworkGroupList>,
     mut query_unit: Query<(
         Option<&mut MatterBinding>, Option<&mut UnderConstruction>,
-        Option<&UpgradeAmplitude>, Option<&UpgradeFrequency>,
+        Option<&UpgradeAmplitude>, Option<&UpgradeFrequency>, Option<&QueuedConstruction>,
     ), (With<GroupLink>, Without<Suspended>)>
 ){
     for mut groups in query_grid.iter_mut() {
         for mut group in groups.iter_mut() {
-            group.summary.matter = group.summary.matter_production + group.summary.matter_reservation;
+            for resource in group.summary.0.iter_mut() {
+                resource.amount = resource.production + resource.reservation;
+            }
 
             for &(_, entity) in group.list.iter() {
                 let Ok((
                     mut matter, construction,
-                    amplitude, frequency,
+                    amplitude, frequency, queued,
                 )) = query_unit.get_mut(entity) else { continue };
+                if queued.is_some() { continue; }
 
                 if let Some(mut construction) = construction {
                     let amplitude = 1 + amplitude.map_or(0,|upgrade|upgrade.0);
                     let frequency = 1 + frequency.map_or(0,|upgrade|upgrade.0);
+                    let summary = &mut group.summary[ResourceVariant::Matter];
 
-                    let delta = group.summary.matter.min(frequency).max(0);
+                    let delta = summary.amount.min(frequency).max(0);
 
-                    group.summary.matter -= delta;
+                    summary.amount -= delta;
                     construction.matter_consumed += amplitude * delta;
                     continue;
                 }
                 if let Some(MatterBinding::Consumption(consumption)) = matter.as_deref_mut() {
-                    consumption.calculated = consumption.quota;
-                    group.summary.matter_consumption += consumption.calculated;
-    
-                    consumption.transfered = group.summary.matter.min(consumption.calculated).max(0);
-                    group.summary.matter -= consumption.transfered;
+                    let requirements = consumption.requirements().collect::<Vec<_>>();
+                    let starving = requirements.iter()
+                        .find(|&&(resource, quota)|group.summary[resource].amount < quota)
+                        .map(|&(resource, _)|resource);
+                    for (resource, quota) in requirements {
+                        let summary = &mut group.summary[resource];
+                        consumption.calculated[resource] += quota;
+                        summary.consumption += quota;
+                        if starving.is_some() { continue; }
+
+                        consumption.transfered[resource] += quota;
+                        summary.amount -= quota;
+                    }
+                    if let (Some(resource), false) = (starving, consumption.starving) {
+                        events.send(EconomyEvent::Starvation(entity, resource));
+                    }
+                    consumption.starving = starving.is_some();
                 }
             }
+
+            let Some(position) = group.queue.iter().position(|&entity|
+                matches!(query_unit.get(entity), Ok((_, Some(_), _, _, Some(_))))
+            ) else { continue };
+            let entity = group.queue[position];
+            let Ok((
+                _, Some(mut construction),
+                amplitude, frequency, _,
+            )) = query_unit.get_mut(entity) else { continue };
+            let amplitude = 1 + amplitude.map_or(0,|upgrade|upgrade.0);
+            let frequency = 1 + frequency.map_or(0,|upgrade|upgrade.0);
+
+            let summary = &mut group.summary[ResourceVariant::Matter];
+            if summary.amount < frequency { continue; }
+            summary.amount -= frequency;
+            construction.matter_consumed += amplitude * frequency;
+            commands.entity(entity).remove::<QueuedConstruction>();
+            group.queue.remove(position);
         }
     }
 }
 
+///Notifies the player once per resource when own consumers run short.
+pub fn report_starvation_phase(
+    mut economy_events: EventReader<EconomyEvent>,
+    mut events: EventWriter<StageEvent>,
+    query_unit: Query<&Agent>,
+){
+    let mut reported: Vec<ResourceVariant> = Vec::new();
+    for &event in economy_events.iter() {
+        let EconomyEvent::Starvation(entity, resource) = event;
+        if reported.contains(&resource) || query_unit.get(entity).map_or(true, |agent|*agent != Agent::Player) { continue; }
+        reported.push(resource);
+        events.send(StageEvent::Message(format!("{} shortage", resource)));
+    }
+}
+
 pub fn collection_phase(
     mut query_grid: Query<&mut NetworkGroupList>,
     mut query_unit: Query<(
@@ -197,27 +322,99 @@ pub fn collection_phase(
 ){
     for mut groups in query_grid.iter_mut() {
         for mut group in groups.iter_mut() {
-            let mut overflow = (group.summary.matter - group.summary.matter_reservation).max(0);
-            let mut ammortization = group.summary.matter_reservation.min(group.summary.matter).max(0);
+            for resource in ResourceVariant::ALL {
+                let summary = &mut group.summary[resource];
+                if !resource.storable() {
+                    summary.amount = 0;
+                    continue;
+                }
+                let mut overflow = (summary.amount - summary.reservation).max(0);
+                let mut ammortization = summary.reservation.min(summary.amount).max(0);
 
-            for &(_, entity) in group.list.iter() {
-                let Ok((
-                    mut matter, amplitude, frequency
-                )) = query_unit.get_mut(entity) else { continue };
-                let MatterBinding::Collection(storage) = matter.as_mut() else { continue };
+                for &(_, entity) in group.list.iter() {
+                    let Ok((
+                        mut matter, amplitude, frequency
+                    )) = query_unit.get_mut(entity) else { continue };
+                    let MatterBinding::Collection(storage) = matter.as_mut() else { continue };
+                    if storage.resource != resource { continue; }
 
-                let frequency = 1 + frequency.map_or(0,|upgrade|upgrade.0);
-                let amplitude = 1 + amplitude.map_or(0,|upgrade|upgrade.0);
+                    let frequency = 1 + frequency.map_or(0,|upgrade|upgrade.0);
+                    let amplitude = 1 + amplitude.map_or(0,|upgrade|upgrade.0);
 
-                let delta_reserved = storage.reserved.min(ammortization / amplitude);
-                ammortization -= delta_reserved * amplitude;
-                storage.stored = storage.stored - storage.reserved + delta_reserved;
+                    let delta_reserved = storage.reserved.min(ammortization / amplitude);
+                    ammortization -= delta_reserved * amplitude;
+                    storage.stored = storage.stored - storage.reserved + delta_reserved;
 
-                let delta_recharge = (storage.recharge * frequency).min(overflow).min(storage.capacity - storage.stored);
-                overflow -= delta_recharge;
-                storage.stored += delta_recharge;
+                    let delta_recharge = (storage.recharge * frequency).min(overflow).min(storage.capacity - storage.stored);
+                    overflow -= delta_recharge;
+                    storage.stored += delta_recharge;
+                }
+                summary.amount = overflow;
             }
-            group.summary.matter = overflow;
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::{NetworkGroup, MapGridTile, PriorityOrder, relink_network_group};
+    use crate::common::adjacency::Graph;
+    use crate::common::geometry::MeshGeometry;
+
+    #[test]
+    pub fn queued_constructions_start_one_per_tick(){
+        let mut world = World::new();
+        world.init_resource::<Events<EconomyEvent>>();
+        let mut construction = UnderConstruction::default();
+        construction.required = 10;
+        let queue: Vec<Entity> = (0..2).map(|_|world.spawn((
+            construction.clone(), QueuedConstruction, GroupLink(Some(0)),
+        )).id()).collect();
+        let mut group = NetworkGroup { queue: queue.clone(), ..Default::default() };
+        group.summary[ResourceVariant::Matter].production = 2;
+        world.spawn(NetworkGroupList(vec![group]));
+
+        let mut schedule = Schedule::new();
+        schedule.add_system(resource_allocation_phase);
+        schedule.run(&mut world);
+        assert!(!world.entity(queue[0]).contains::<QueuedConstruction>());
+        assert!(world.entity(queue[1]).contains::<QueuedConstruction>());
+        schedule.run(&mut world);
+        assert!(!world.entity(queue[1]).contains::<QueuedConstruction>());
+    }
+
+    #[test]
+    pub fn production_keeps_network_groups(){
+        let mut world = World::new();
+        world.insert_resource(GlobalEconomy { density: vec![1], ..Default::default() });
+        world.init_resource::<StrategySettings>();
+        let geometry = MeshGeometry { vertices: vec![bevy::math::Vec3A::ZERO; 3], indices: vec![0, 1, 2] };
+        let mut grid = MapGrid { tiles: vec![MapGridTile::default(); 3], graph: Graph::from(&geometry), ..Default::default() };
+        let extractor = world.spawn((
+            GridTileIndex(0), Agent::Player, GroupLink::default(), PriorityOrder(0),
+            MatterBinding::Production(MatterProduction { efficiency: 1, ..Default::default() }),
+        )).id();
+        grid.tiles[0].set_entity(extractor);
+        let parent = world.spawn((grid, NetworkGroupList::default())).push_children(&[extractor]).id();
+
+        let mut schedule = Schedule::new();
+        schedule.add_systems((relink_network_group, production_phase).chain());
+        schedule.run(&mut world);
+        assert_eq!(world.get::<NetworkGroupList>(parent).unwrap().len(), 1);
+        //a stale group survives only as long as the grid is not relinked
+        world.get_mut::<NetworkGroupList>(parent).unwrap().push(NetworkGroup::default());
+        schedule.run(&mut world);
+        schedule.run(&mut world);
+        let groups = world.get::<NetworkGroupList>(parent).unwrap();
+        assert_eq!(groups.len(), 2);
+        assert!(groups[0].summary[ResourceVariant::Matter].production > 0);
+
+        let mut grid = world.get_mut::<MapGrid>(parent).unwrap();
+        let tile = &mut grid.bypass_change_detection().tiles[0];
+        (tile.capacity, tile.reserve) = (4, 4);
+        for _ in 0..8 { schedule.run(&mut world); }
+        assert_eq!(world.get::<NetworkGroupList>(parent).unwrap().len(), 2);
+        assert_eq!(world.get::<MapGrid>(parent).unwrap().tiles[0].reserve, 0);
+    }
+}
@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use super::economy::ResourceVariant;
 
 #[derive(Clone, PartialEq)]
 pub enum ConstructionEvent {
@@ -13,4 +14,16 @@ pub enum CombatEvent {
     Hit(Entity),
     ProjectileLaunch(Entity, Entity, Entity),
     ProjectileHit(Entity, Entity),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum EconomyEvent {
+    ///Consumer stopped receiving its full quota of the resource.
+    Starvation(Entity, ResourceVariant),
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub enum StageEvent {
+    ///Text shown to the player by a stage trigger.
+    Message(String),
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use bevy::utils::HashMap;
 use crate::common::animation::ease::lerp;
 
 #[derive(Component, Clone, Default)]
@@ -6,7 +7,8 @@ pub struct FabricationGate {
     pub filter: u8,
     pub elapsed: i32,
     pub released: i32,
-    pub last_released: std::time::Duration,
+    ///Simulation tick of the last released unit.
+    pub last_released: u64,
     pub limit: i32,
     pub path: Vec<usize>,
 }
@@ -24,6 +26,7 @@ pub struct UnitFabrication {
 impl UnitFabrication {
     pub const MILITARY: u8 = 0x01;
     pub const CIVILIAN: u8 = 0x02;
+    pub const LOGISTIC: u8 = 0x04;
     pub fn calculate(&self, fraction: f32) -> f32 {
         lerp(self.prev_consumed as f32, self.consumed as f32, fraction) / self.required as f32
     }
@@ -34,15 +37,17 @@ impl UnitFabrication {
 use crate::common::loader::AssetBundle;
 use crate::scene::{BlueprintAssetBundle, UnitBlueprint, ModelAssetBundle};
 use crate::interaction::construct_unit;
-use crate::logic::{Agent, MapGrid, Integrity, UnderConstruction, Suspended, MatterBinding, GroupLink, NetworkGroupList, MilitarySupply};
-use crate::logic::{FollowingPath, LandingProbe};
+use crate::logic::{Agent, MapGrid, Integrity, UnderConstruction, Suspended, MatterBinding, MatterStorage, ResourceVariant, GroupLink, NetworkGroupList, MilitarySupply};
+use crate::extensions::CommandsExtension;
+use crate::logic::{FollowingPath, LandingProbe, MatterCarrier, ArmyReserve, StrategySettings, SimulationTick};
 use crate::logic::{UpgradeAmplitude, UpgradeFrequency, UpgradeRange};
 
 pub fn fabrication_phase(
-    time: Res<Time>,
+    tick: Res<SimulationTick>,
+    settings: Res<StrategySettings>,
     mut commands: Commands,
     blueprint_bundle: Res<AssetBundle<BlueprintAssetBundle>>,
-    model_bundle: Res<AssetBundle<ModelAssetBundle>>,
+    model_bundle: Option<Res<AssetBundle<ModelAssetBundle>>>,
     blueprints: Res<Assets<UnitBlueprint>>,
     query_grid: Query<(&MapGrid, &NetworkGroupList)>,
     mut query_unit: ParamSet<(
@@ -50,13 +55,30 @@ pub fn fabrication_phase(
         Query<(
             &Parent, &Agent, &GroupLink, &MatterBinding, &mut UnitFabrication,
             Option<&UpgradeAmplitude>, Option<&UpgradeFrequency>, Option<&UpgradeRange>
-        ), (Without<FabricationGate>, Without<UnderConstruction>, Without<Suspended>)>
+        ), (Without<FabricationGate>, Without<UnderConstruction>, Without<Suspended>)>,
+        Query<&mut MatterBinding, (With<GroupLink>, Without<UnderConstruction>)>,
     )>,
     mut query_gate: Query<&mut FabricationGate, (Without<Suspended>, With<GroupLink>)>
 ){
     for mut fabrication in query_unit.p0().iter_mut() {
         fabrication.prev_consumed = fabrication.consumed;
     }
+    let mut cargo: HashMap<(Entity, usize), i32> = HashMap::default();
+    for (parent, _, group, _, fabrication, ..) in query_unit.p1().iter() {
+        if fabrication.group != UnitFabrication::LOGISTIC || !fabrication.is_ready() { continue; }
+        let Some(group_index) = group.0 else { continue };
+        cargo.insert((parent.get(), group_index), 0);
+    }
+    for (&(parent, group_index), stored) in cargo.iter_mut() {
+        let Ok((_, groups)) = query_grid.get(parent) else { continue };
+        let query_storage = query_unit.p2();
+        *stored = groups[group_index].list.iter().filter_map(|&(_, entity)|match query_storage.get(entity) {
+            Ok(MatterBinding::Collection(storage)) if storage.resource == ResourceVariant::Matter => Some(storage.stored),
+            _ => None
+        }).sum();
+    }
+    let loaded = cargo.clone();
+
     for (
         parent, agent, group, matter, mut fabrication,
         amplitude, frequency, range
@@ -79,7 +101,8 @@ pub fn fabrication_phase(
         }
 
         let Ok((grid, groups)) = query_grid.get(parent.get()) else { continue };
-        let Some(group) = group.0.map(|i|&groups[i]) else { continue };
+        let Some(group_index) = group.0 else { continue };
+        let group = &groups[group_index];
 
         let Some((&tile_index, &target_entity,_)) = group.list.iter()
         .filter_map(|(index, entity)|query_gate.get(*entity).ok()
@@ -89,28 +112,65 @@ pub fn fabrication_phase(
         let Ok(mut gate) = query_gate.get_mut(target_entity) else { continue };
         if gate.filter & fabrication.group == 0 { continue; }
 
+        let storage = match blueprint.matter.as_ref() {
+            Some(MatterBinding::Collection(storage)) => Some(storage),
+            _ => None
+        };
+        if fabrication.group == UnitFabrication::LOGISTIC &&
+            (storage.is_none() || cargo.get(&(parent.get(), group_index)).map_or(true, |&stored|stored <= 0)) { continue; }
+
         fabrication.consumed = 0;
         gate.released += 1;
-        gate.last_released = time.elapsed();
+        gate.last_released = tick.elapsed;
         for _i in 0..(fabrication.batch as i32 * (1 + amplitude)) {
+            let load = match (fabrication.group, storage, cargo.get_mut(&(parent.get(), group_index))) {
+                (UnitFabrication::LOGISTIC, Some(storage), Some(stored)) if *stored > 0 => {
+                    let load = storage.capacity.min(*stored);
+                    *stored -= load;
+                    Some(MatterStorage { stored: load, ..storage.clone() })
+                },
+                (UnitFabrication::LOGISTIC, _, _) => break,
+                _ => None
+            };
             let entity = construct_unit(
-                &mut commands, parent.get(), &grid, &model_bundle, &blueprints,
+                &mut commands, parent.get(), &grid, model_bundle.as_deref(), &blueprints,
                 (blueprint_handle.clone(), *agent, tile_index)
             );
             commands.entity(entity).insert(FollowingPath::from(gate.path.clone()));
             if fabrication.group == UnitFabrication::CIVILIAN {
                 commands.entity(entity).insert(LandingProbe::default());
+            } else if let Some(load) = load {
+                commands.entity(entity).insert(MatterCarrier).insert_add(MatterBinding::Collection(load));
             } else if fabrication.group == UnitFabrication::MILITARY {
                 commands.entity(entity).insert(MilitarySupply {
                     amplitude, frequency, range, snapshot: true
                 });
+                if settings.profile(agent).is_some() {
+                    commands.entity(entity).insert(ArmyReserve);
+                }
             }
         }
     }
+
+    for (&(parent, group_index), &remaining) in cargo.iter() {
+        let mut debit = loaded[&(parent, group_index)] - remaining;
+        let Ok((_, groups)) = query_grid.get(parent) else { continue };
+        let mut query_storage = query_unit.p2();
+        for &(_, entity) in groups[group_index].list.iter() {
+            if debit <= 0 { break; }
+            let Ok(mut matter) = query_storage.get_mut(entity) else { continue };
+            let MatterBinding::Collection(storage) = matter.as_mut() else { continue };
+            if storage.resource != ResourceVariant::Matter { continue; }
+            let delta = debit.min(storage.stored).max(0);
+            storage.stored -= delta;
+            debit -= delta;
+        }
+    }
 }
 
 pub fn expiration_phase(
-    time: Res<Time>,
+    tick: Res<SimulationTick>,
+    fixed_time: Res<FixedTime>,
     mut commands: Commands,
     mut query: ParamSet<(
         Query<(&mut Integrity, &MatterBinding)>,
@@ -120,12 +180,12 @@ pub fn expiration_phase(
     for (mut integrity, matter) in query.p0().iter_mut() {
         let MatterBinding::Collection(collection) = matter else { continue };
         if collection.stored == 0 && collection.recharge == 0 {
-            integrity.apply_damage(i32::MAX);
+            integrity.destroy();
         }
     }
     for (entity, gate) in query.p1().iter() {
         if gate.limit > 0 && gate.released >= gate.limit {
-            let elapsed = (time.elapsed() - gate.last_released).as_secs_f32();
+            let elapsed = (tick.elapsed - gate.last_released) as f32 * fixed_time.period.as_secs_f32();
             if elapsed > 2.0 {
                 commands.entity(entity).remove::<FabricationGate>();
             }
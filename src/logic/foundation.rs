@@ -2,10 +2,34 @@ use bevy::prelude::*;
 use std::ops::AddAssign;
 use crate::common::animation::ease::lerp;
 
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum DamageVariant {
+    #[default] Kinetic,
+    Corrosive,
+    Energy,
+}
+
+///Fraction of each damage variant absorbed, negative values amplify the damage.
+#[derive(serde::Deserialize, Clone, Default, Debug)]
+pub struct DamageResistance {
+    #[serde(default)] pub kinetic: f32,
+    #[serde(default)] pub corrosive: f32,
+    #[serde(default)] pub energy: f32,
+}
+impl DamageResistance {
+    pub fn get(&self, variant: DamageVariant) -> f32 { match variant {
+        DamageVariant::Kinetic => self.kinetic,
+        DamageVariant::Corrosive => self.corrosive,
+        DamageVariant::Energy => self.energy,
+    } }
+    pub fn average(&self) -> f32 { (self.kinetic + self.corrosive + self.energy) / 3.0 }
+}
+
 #[derive(Component, serde::Deserialize, Clone, Default, Debug)]
 pub struct Integrity {
     pub max: i32,
     pub rate: i32,
+    #[serde(default)] pub resistance: DamageResistance,
     #[serde(default, skip)] pub prev_max: i32,
     #[serde(default, skip)] pub absorbed: i32,
     #[serde(default, skip)] pub restored: i32,
@@ -24,13 +48,24 @@ impl AddAssign<Integrity> for Integrity {
             (self.prev_restored / self.rate) * rhs.rate + self.prev_restored % self.rate
         };
         self.rate = rhs.rate;
+        self.resistance = rhs.resistance;
     }
 }
 
 impl Integrity {
-    pub fn apply_damage(&mut self, damage: i32){
+    ///Resistance to the damage variant and terrain defence both reduce the damage by a fraction.
+    ///Reduced damage deals at least one point, only full resistance prevents it.
+    pub fn apply_damage(&mut self, damage: i32, variant: DamageVariant, defence: f32){
+        let multiplier = (1.0 - self.resistance.get(variant)).max(0.0) * (1.0 - defence).max(0.0);
+        let damage = match (damage as f32 * multiplier).round() as i32 {
+            0 if damage > 0 && multiplier > 0.0 => 1,
+            damage => damage,
+        };
         self.absorbed = self.absorbed.checked_add(damage).unwrap_or(i32::MAX);
     }
+    pub fn destroy(&mut self){
+        self.absorbed = i32::MAX;
+    }
     pub fn get_restored(&self, fraction: f32) -> f32 {
         if self.rate == 0 { 0.0 } else {
             lerp(self.prev_restored as f32, self.restored as f32, fraction) / self.rate as f32
@@ -54,6 +89,10 @@ impl Integrity {
         (max - damaged) / self.max as f32
     }
     pub fn tier(&self) -> i32 { self.max / 10 }
+    ///Tier of the integrity scaled by the average resistance.
+    pub fn effective_tier(&self) -> i32 {
+        (self.max as f32 / (1.0 - self.resistance.average()).max(0.1)) as i32 / 10
+    }
 }
 
 #[derive(Component, serde::Deserialize, Clone, Default, Debug)]
@@ -77,7 +116,11 @@ impl UnderConstruction {
 #[derive(Component, Clone, Default, Debug)]
 pub struct Suspended;
 
-use crate::logic::{MapGrid, GridTileIndex, ConstructionEvent, CombatEvent, GroupLink};
+///Construction reserving its tile until the group has matter to spare.
+#[derive(Component, Clone, Default, Debug)]
+pub struct QueuedConstruction;
+
+use crate::logic::{MapGrid, GridTileIndex, ConstructionEvent, CombatEvent, GroupLink, UpgradeRegenerate};
 pub fn construction_phase(
     mut events: EventWriter<ConstructionEvent>,
     mut commands: Commands,
@@ -121,11 +164,35 @@ pub fn destruction_phase(
 }
 
 pub fn reconstruction_phase(
-    mut query_unit: Query<&mut Integrity, (With<GroupLink>, Without<UnderConstruction>, Without<Suspended>)>
+    mut query_unit: Query<(&mut Integrity, Option<&UpgradeRegenerate>), (With<GroupLink>, Without<UnderConstruction>, Without<Suspended>)>
 ){
-    for mut integrity in query_unit.iter_mut() {
+    for (mut integrity, regenerate) in query_unit.iter_mut() {
         if integrity.rate > 0 && integrity.restored / integrity.rate < integrity.absorbed {
-            integrity.restored += 1;
+            let limit = integrity.absorbed.saturating_mul(integrity.rate);
+            let step = 1 + regenerate.map_or(0,|upgrade|upgrade.0);
+            integrity.restored = (integrity.restored + step).min(limit).max(integrity.restored + 1);
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn resistance_reduces_damage(){
+        let mut integrity = Integrity {
+            max: 10, resistance: DamageResistance { kinetic: 0.75, corrosive: -0.5, energy: 1.0 }, ..Default::default()
+        };
+        integrity.apply_damage(1, DamageVariant::Kinetic, 0.0);
+        assert_eq!(integrity.absorbed, 1);
+        integrity.apply_damage(1, DamageVariant::Kinetic, 0.5);
+        assert_eq!(integrity.absorbed, 2);
+        integrity.apply_damage(4, DamageVariant::Kinetic, 0.0);
+        assert_eq!(integrity.absorbed, 3);
+        integrity.apply_damage(2, DamageVariant::Corrosive, 0.0);
+        assert_eq!(integrity.absorbed, 6);
+        integrity.apply_damage(4, DamageVariant::Energy, 0.0);
+        assert_eq!(integrity.absorbed, 6);
+    }
+}
@@ -1,6 +1,7 @@
 use bevy::prelude::*;
 use super::agent::Agent;
 use super::economy::EconomySummary;
+use super::foundation::QueuedConstruction;
 use super::terrain::grid::{MapGrid,GridTileIndex};
 
 #[derive(Clone, Default)]
@@ -8,6 +9,8 @@ pub struct NetworkGroup {
     pub agent: Agent,
     pub list: Vec<(usize, Entity)>,
     pub summary: EconomySummary,
+    ///Queued constructions in priority order.
+    pub queue: Vec<Entity>,
 }
 
 #[derive(Component, Deref, DerefMut, Clone, Default)]
@@ -16,12 +19,28 @@ pub struct NetworkGroupList(pub Vec<NetworkGroup>);
 #[derive(Component, Deref, DerefMut, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct PriorityOrder(pub u64);
 
+///Allocation tier set by the player, structures within a tier are served in priority order.
+#[derive(Component, serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub enum PriorityLevel {
+    Critical,
+    #[default] Normal,
+    Low,
+}
+impl PriorityLevel {
+    pub fn next(&self) -> Self { match self {
+        PriorityLevel::Critical => PriorityLevel::Normal,
+        PriorityLevel::Normal => PriorityLevel::Low,
+        PriorityLevel::Low => PriorityLevel::Critical,
+    } }
+}
+
 #[derive(Component, Deref, DerefMut, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct GroupLink(pub Option<usize>);
 
 pub fn relink_network_group(
     mut query_grid: Query<(&Children, &mut MapGrid, &mut NetworkGroupList), Changed<MapGrid>>,
-    mut query_unit: Query<(Entity, &GridTileIndex, &Agent, &mut GroupLink, &PriorityOrder), With<GroupLink>>,
+    mut query_unit: Query<(Entity, &GridTileIndex, &Agent, &mut GroupLink, (&PriorityOrder, Option<&PriorityLevel>)), With<GroupLink>>,
+    query_queued: Query<(), With<QueuedConstruction>>,
 ){
     for (children, mut grid, mut groups) in query_grid.iter_mut() {
         let grid = grid.bypass_change_detection();
@@ -38,16 +57,59 @@ pub fn relink_network_group(
                 .filter(|value|agent.eq(value.2))
             ) else { continue };
 
-            list.sort_by_key(|item|item.4.0);
-            let list = list.into_iter()
+            list.sort_by_key(|(_, _, _, _, (order, level))|(level.copied().unwrap_or_default(), order.0));
+            let list: Vec<(usize, Entity)> = list.into_iter()
             .map(|(entity,tile_index,_,_,_)|(**tile_index, entity)).collect();
-            let group = NetworkGroup { list, agent, ..Default::default() };
+            let queue = list.iter().map(|&(_, entity)|entity)
+            .filter(|&entity|query_queued.contains(entity)).collect();
+            let group = NetworkGroup { list, agent, queue, ..Default::default() };
             for (_, entity) in group.list.iter() {
                 let Ok(mut group) = query_unit.get_component_mut::<GroupLink>(*entity) else { continue };
                 group.replace(group_index);
             }
             groups.push(group);
         }
-        println!("relinking network groups ({})", groups.len());
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::adjacency::Graph;
+    use crate::common::geometry::MeshGeometry;
+    use crate::logic::MapGridTile;
+
+    ///Three mutually adjacent tiles, each occupied by a structure of the player.
+    fn spawn_grid(world: &mut World, levels: [PriorityLevel; 3]) -> (Entity, Vec<Entity>) {
+        let geometry = MeshGeometry { vertices: vec![bevy::math::Vec3A::ZERO; 3], indices: vec![0, 1, 2] };
+        let mut grid = MapGrid { tiles: vec![MapGridTile::default(); 3], graph: Graph::from(&geometry), ..Default::default() };
+        let units: Vec<Entity> = levels.into_iter().enumerate().map(|(index, level)|{
+            let entity = world.spawn((
+                GridTileIndex(index), Agent::Player, GroupLink::default(), PriorityOrder(index as u64), level,
+            )).id();
+            grid.tiles[index].set_entity(entity);
+            entity
+        }).collect();
+        let parent = world.spawn((grid, NetworkGroupList::default())).push_children(&units).id();
+        (parent, units)
+    }
+
+    fn relink_order(levels: [PriorityLevel; 3]) -> Vec<usize> {
+        let mut world = World::new();
+        let (parent, units) = spawn_grid(&mut world, levels);
+        let mut schedule = Schedule::new();
+        schedule.add_system(relink_network_group);
+        schedule.run(&mut world);
+        let groups = world.get::<NetworkGroupList>(parent).unwrap();
+        assert_eq!(groups.len(), 1);
+        groups[0].list.iter().map(|(_, entity)|units.iter().position(|unit|unit == entity).unwrap()).collect()
+    }
+
+    #[test]
+    pub fn relink_orders_by_priority_level(){
+        use PriorityLevel::*;
+        assert_eq!(relink_order([Low, Normal, Critical]), vec![2, 1, 0]);
+        assert_eq!(relink_order([Normal, Critical, Normal]), vec![1, 0, 2]);
+        assert_eq!(relink_order([Normal, Normal, Normal]), vec![0, 1, 2]);
+    }
+}
@@ -0,0 +1,52 @@
+use bevy::prelude::*;
+use crate::logic::{Agent, AllianceTable, MapGrid, GridTileIndex, GroupLink, NetworkGroupList};
+use crate::logic::{MatterBinding, UnderConstruction, FollowingPath};
+
+///Mobile unit unloading its stored matter into an allied group at the end of its path.
+#[derive(Component, Clone, Default)]
+pub struct MatterCarrier;
+
+pub fn unload_carrier_phase(
+    alliances: Res<AllianceTable>,
+    query_grid: Query<(&MapGrid, &NetworkGroupList)>,
+    mut query_unit: ParamSet<(
+        Query<(Entity, &Parent, &Agent, &GridTileIndex, &mut MatterBinding), (With<MatterCarrier>, Without<FollowingPath>)>,
+        Query<&mut MatterBinding, (With<GroupLink>, Without<UnderConstruction>)>,
+    )>,
+){
+    let mut deliveries: Vec<(Entity, Entity, usize)> = Vec::new();
+    for (entity, parent, agent, tile_index, matter) in query_unit.p0().iter() {
+        let MatterBinding::Collection(cargo) = matter else { continue };
+        if cargo.stored <= 0 { continue; }
+        let Ok((grid, groups)) = query_grid.get(parent.get()) else { continue };
+        let Some(group_index) = std::iter::once(tile_index.0)
+            .chain(grid.graph.neighbors(tile_index.0).into_iter().flatten().copied())
+            .filter_map(|index|grid.visited.get(&index).copied())
+            .find(|&group_index|alliances.is_allied(&groups[group_index].agent, agent)) else { continue };
+        deliveries.push((entity, parent.get(), group_index));
+    }
+    for (entity, parent, group_index) in deliveries.into_iter() {
+        let Ok((_, groups)) = query_grid.get(parent) else { continue };
+        let Some((resource, mut remaining)) = query_unit.p0().get(entity).ok().and_then(|(_, _, _, _, matter)|match matter {
+            MatterBinding::Collection(cargo) => Some((cargo.resource, cargo.stored)),
+            _ => None
+        }) else { continue };
+        let loaded = remaining;
+
+        for &(_, entity) in groups[group_index].list.iter() {
+            if remaining <= 0 { break; }
+            let mut query_storage = query_unit.p1();
+            let Ok(mut matter) = query_storage.get_mut(entity) else { continue };
+            let MatterBinding::Collection(storage) = matter.as_mut() else { continue };
+            if storage.resource != resource { continue; }
+            let delta = remaining.min(storage.capacity - storage.stored).max(0);
+            storage.stored += delta;
+            remaining -= delta;
+        }
+
+        let mut query_carrier = query_unit.p0();
+        let Ok((_, _, _, _, mut matter)) = query_carrier.get_mut(entity) else { continue };
+        let MatterBinding::Collection(cargo) = matter.as_mut() else { continue };
+        cargo.stored -= loaded - remaining;
+    }
+}
@@ -1,10 +1,10 @@
 use std::time::Duration;
 use bevy::prelude::*;
-use bevy::utils::FloatOrd;
 use bevy::ecs::query::ReadOnlyWorldQuery;
 use crate::extensions::CommandsExtension;
 use crate::interaction::ActionSelector;
-use crate::logic::{CombatEvent, SpatialLookupGrid, UpgradeVariant, DegradeImmobilize};
+use crate::common::animation::ease::lerp;
+use crate::logic::{CombatEvent, SpatialLookupGrid, UpgradeVariant, DegradeImmobilize, VisibilityLookup};
 
 #[derive(serde::Deserialize, Clone, PartialEq, Eq, Debug)]
 pub enum UnitDirective {
@@ -40,13 +40,16 @@ pub enum MilitaryBinding {
         radius: (f32, f32),
         cooldown: f32,
         damage: i32,
+        #[serde(default)] variant: DamageVariant,
         #[serde(default, skip)] cooldown_timer: Timer,
         #[serde(default, skip)] orientation: Quat,
+        #[serde(default, skip)] prev_orientation: Quat,
     },
     Connection {
         radius: (f32, f32),
         limit: i32,
         damage: i32,
+        #[serde(default)] variant: DamageVariant,
         rate: f32,
         degrade: Option<UpgradeVariant>,
         #[serde(default, skip)] released: i32,
@@ -59,6 +62,14 @@ pub enum MilitaryBinding {
         radius: (f32, f32),
         area: f32,
         damage: i32,
+        #[serde(default)] variant: DamageVariant,
+    },
+    ///Absorbs damage to allied units within the radius while supplied.
+    Barrier {
+        radius: (f32, f32),
+        capacity: i32,
+        recharge: i32,
+        #[serde(default, skip)] stored: i32,
     },
 }
 impl MilitaryBinding {
@@ -66,7 +77,8 @@ impl MilitaryBinding {
         MilitaryBinding::Trajectory { radius, .. } |
         MilitaryBinding::Connection { radius, .. } |
         MilitaryBinding::Area { radius, .. } |
-        MilitaryBinding::Impact { radius, .. } => radius.1,
+        MilitaryBinding::Impact { radius, .. } |
+        MilitaryBinding::Barrier { radius, .. } => radius.1,
     } }
     pub fn is_close_range(&self) -> bool { match self {
         MilitaryBinding::Impact { .. } => true,
@@ -80,11 +92,31 @@ pub struct TargetLock(Entity);
 #[derive(Component, Deref, DerefMut, Clone)]
 pub struct SourceLink(Entity);
 
-#[derive(serde::Deserialize, Component, Clone)]
+#[derive(serde::Deserialize, Component, Clone, Default)]
 pub struct TrajectoryEffect {
     pub linked: bool,
     pub intro: Timer,
     pub outro: Timer,
+    #[serde(default, skip)] pub prev_intro: f32,
+    #[serde(default, skip)] pub prev_outro: f32,
+}
+impl TrajectoryEffect {
+    fn store_previous(&mut self){
+        self.prev_intro = self.intro.percent();
+        self.prev_outro = self.outro.percent();
+    }
+    ///Intro and outro progress interpolated between fixed ticks.
+    pub fn calculate(&self, fraction: f32) -> (f32, f32) {
+        (lerp(self.prev_intro, self.intro.percent(), fraction), lerp(self.prev_outro, self.outro.percent(), fraction))
+    }
+}
+impl MilitaryBinding {
+    ///Orientation of the trajectory turret interpolated between fixed ticks.
+    pub fn calculate_orientation(&self, fraction: f32) -> Option<Quat> { match self {
+        MilitaryBinding::Trajectory { orientation, prev_orientation, .. } =>
+            Some(Quat::slerp(*prev_orientation, *orientation, fraction)),
+        _ => None
+    } }
 }
 
 #[derive(serde::Deserialize, Component, Clone, Debug)]
@@ -92,33 +124,74 @@ pub enum ImpactEffect {
     Single {
         interval: Timer,
         damage: i32,
+        #[serde(default)] variant: DamageVariant,
     },
     Area {
         interval: Timer,
         damage: i32,
+        #[serde(default)] variant: DamageVariant,
         radius: f32,
     }
 }
 
-use crate::logic::{Agent, Suspended, UnderConstruction, MatterBinding, Integrity, FollowingPath};
+use crate::logic::{Agent, AllianceTable, Suspended, UnderConstruction, MatterBinding, Integrity, DamageVariant, FollowingPath};
 use crate::logic::{UpgradeAmplitude, UpgradeFrequency, UpgradeRange};
 
+fn terrain_defence(
+    terrain: &TerrainVariantTable, query_grid: &Query<&MapGrid>,
+    parent: Option<&Parent>, tile_index: Option<&GridTileIndex>
+) -> f32 {
+    let (Some(parent), Some(tile_index)) = (parent, tile_index) else { return 0.0 };
+    query_grid.get(parent.get()).map_or(0.0, |grid|terrain.defence(grid.tiles[**tile_index].variant))
+}
+
+fn absorb_barrier_damage(
+    barriers: &[(Entity, Agent, Vec3, f32)], query_source: &mut Query<&mut MilitaryBinding>,
+    alliances: &AllianceTable, agent: &Agent, position: Vec3, mut damage: i32,
+) -> i32 {
+    for (entity, barrier_agent, center, radius) in barriers.iter() {
+        if damage <= 0 { break; }
+        if !alliances.is_allied(barrier_agent, agent) { continue; }
+        if center.distance_squared(position) > radius * radius { continue; }
+        let Ok(mut military) = query_source.get_mut(*entity) else { continue };
+        let MilitaryBinding::Barrier { stored, .. } = military.as_mut() else { continue };
+        let absorbed = damage.min(*stored);
+        *stored -= absorbed;
+        damage -= absorbed;
+    }
+    damage
+}
+
 pub fn apply_combat_damage(
-    time: Res<Time>,
+    fixed_time: Res<FixedTime>,
+    alliances: Res<AllianceTable>,
+    terrain: Res<TerrainVariantTable>,
     lookup: Res<SpatialLookupGrid<Entity>>,
     mut commands: Commands,
     mut events: EventWriter<CombatEvent>,
-    mut query_unit: Query<&mut Integrity>,
+    query_grid: Query<&MapGrid>,
+    mut query_unit: Query<(&mut Integrity, Option<&Parent>, Option<&GridTileIndex>)>,
     mut query_source: Query<&mut MilitaryBinding>,
     query_supply: Query<&MilitarySupply, With<GroupLink>>,
+    query_barrier: Query<(Entity, &Agent, &GlobalTransform, &MilitarySupply), (With<MilitaryBinding>, With<GroupLink>)>,
     mut query: ParamSet<(
         Query<(Entity, Option<&SourceLink>, &mut TrajectoryEffect), Without<ImpactEffect>>,
         Query<(Entity, &TargetLock, Option<&SourceLink>, &mut ImpactEffect, Option<&mut TrajectoryEffect>)>,
     )>,
     query_target: Query<(&Agent, &GlobalTransform)>
 ){
+    let delta = fixed_time.period;
+    let barriers: Vec<(Entity, Agent, Vec3, f32)> = query_barrier.iter()
+    .filter_map(|(entity, agent, transform, supply)|match query_source.get(entity) {
+        Ok(military @ MilitaryBinding::Barrier { stored, .. }) if *stored > 0 => Some((
+            entity, *agent, transform.translation(), military.radius() * supply.range_multipler()
+        )),
+        _ => None
+    }).collect();
+
     for (entity, source, mut trajectory) in query.p0().iter_mut() {
-        trajectory.outro.tick(time.delta());
+        trajectory.store_previous();
+        trajectory.outro.tick(delta);
         if !trajectory.outro.finished() { continue; }
 
         if let Some(MilitaryBinding::Connection { released, .. }) = source.and_then(|entity|
@@ -130,21 +203,26 @@ pub fn apply_combat_damage(
         commands.entity(entity).despawn_recursive();
     }
     for (entity, target, source, mut impact, mut trajectory) in query.p1().iter_mut() {
+        if let Some(trajectory) = trajectory.as_deref_mut() { trajectory.store_previous(); }
         let relevant = match (source, trajectory.as_ref()) {
             (Some(entity), Some(trajectory)) => !trajectory.linked || query_supply.contains(**entity),
             _ => true
         };
         if relevant {
             if let Some(trajectory) = trajectory.as_deref_mut() {
-                trajectory.intro.tick(time.delta());
+                trajectory.intro.tick(delta);
                 if !trajectory.intro.finished() { continue; }
             }
             match impact.as_mut() {
-                ImpactEffect::Single { interval, damage } => {
-                    interval.tick(time.delta());
+                ImpactEffect::Single { interval, damage, variant } => {
+                    interval.tick(delta);
                     if !interval.just_finished() { continue; }
-                    if let Ok(mut integrity) = query_unit.get_mut(**target) {
-                        integrity.apply_damage(*damage);
+                    if let Ok((mut integrity, parent, tile_index)) = query_unit.get_mut(**target) {
+                        let damage = *damage * interval.times_finished_this_tick() as i32;
+                        let damage = query_target.get(**target).map_or(damage, |(target_agent, target_transform)|absorb_barrier_damage(
+                            &barriers, &mut query_source, &alliances, target_agent, target_transform.translation(), damage
+                        ));
+                        integrity.apply_damage(damage, *variant, terrain_defence(&terrain, &query_grid, parent, tile_index));
                         if interval.mode() == TimerMode::Once {
                             events.send(CombatEvent::ProjectileHit(entity, **target));
                         } else {
@@ -153,21 +231,22 @@ pub fn apply_combat_damage(
                         if interval.mode() == TimerMode::Repeating { continue; }
                     }
                 },
-                ImpactEffect::Area { interval, damage, radius } => {
-                    interval.tick(time.delta());
+                ImpactEffect::Area { interval, damage, variant, radius } => {
+                    interval.tick(delta);
                     if !interval.just_finished() { continue; }
 
-                    if let Some(mut integrity) = source.and_then(|source|query_unit.get_mut(source.0).ok()) {
-                        integrity.apply_damage(i32::MAX);
+                    if let Some((mut integrity, _, _)) = source.and_then(|source|query_unit.get_mut(source.0).ok()) {
+                        integrity.destroy();
                     }
 
                     let Ok((agent, transform)) = query_target.get(entity) else { continue };
-                    for (entity, _center) in lookup.query_around(transform.translation(), *radius) {
+                    for (entity, center) in lookup.query_around(transform.translation(), *radius) {
                         let Ok(target_agent) = query_target.get_component::<Agent>(*entity) else { continue };
-                        if target_agent.eq(agent) { continue; }
-                        let Ok(mut integrity) = query_unit.get_mut(*entity) else { continue };
+                        if !alliances.is_hostile(agent, target_agent) { continue; }
+                        let Ok((mut integrity, parent, tile_index)) = query_unit.get_mut(*entity) else { continue };
                         events.send(CombatEvent::Hit(*entity));
-                        integrity.apply_damage(*damage);
+                        let damage = absorb_barrier_damage(&barriers, &mut query_source, &alliances, target_agent, *center, *damage);
+                        integrity.apply_damage(damage, *variant, terrain_defence(&terrain, &query_grid, parent, tile_index));
                     }
                 }
             }
@@ -206,8 +285,19 @@ pub fn resupply_military_phase(
     }
 }
 
+pub fn recharge_barrier_phase(
+    mut query_unit: Query<(&mut MilitaryBinding, &MilitarySupply)>,
+){
+    for (mut military, supply) in query_unit.iter_mut() {
+        let MilitaryBinding::Barrier { capacity, recharge, stored, .. } = military.as_mut() else { continue };
+        *stored = (*stored + *recharge * (1 + supply.frequency)).min(*capacity + supply.amplitude);
+    }
+}
+
 pub fn query_next_target<'a, T: ReadOnlyWorldQuery>(
     lookup: &SpatialLookupGrid<Entity>,
+    alliances: &AllianceTable,
+    visibility: &VisibilityLookup,
     agent: &Agent,
     transform: &GlobalTransform,
     query_target: &'a Query<(Entity, &Agent, &GlobalTransform), T>,
@@ -221,7 +311,8 @@ pub fn query_next_target<'a, T: ReadOnlyWorldQuery>(
         let distance_squared = transform.translation().distance_squared(position);
         if distance_squared < min || distance_squared > max { continue; }
         let Ok(row) = query_target.get(entity) else { continue };
-        if agent.eq(row.1) { continue; }
+        if !alliances.is_hostile(agent, row.1) { continue; }
+        if !visibility.is_visible(agent, entity) { continue; }
         if optimal > distance_squared {
             optimal = distance_squared;
             closest = Some(row);
@@ -232,11 +323,13 @@ pub fn query_next_target<'a, T: ReadOnlyWorldQuery>(
 
 pub fn update_military_targeting(
     lookup: Res<SpatialLookupGrid<Entity>>,
-    time: Res<Time>,
+    alliances: Res<AllianceTable>,
+    visibility: VisibilityLookup,
+    fixed_time: Res<FixedTime>,
     mut commands: Commands,
     mut events: EventWriter<CombatEvent>,
     mut query_unit: Query<(
-        Entity, &Parent, &Agent, &mut MilitaryBinding, &MilitarySupply,
+        Entity, &Parent, &Agent, &mut MilitaryBinding, Option<&MilitarySupply>,
         Option<&TargetLock>, &GlobalTransform,
     )>,
     query_target: Query<(Entity, &Agent, &GlobalTransform), With<Integrity>>,
@@ -245,24 +338,29 @@ pub fn update_military_targeting(
         entity, parent, agent, mut military, supply,
         target_lock, transform,
     ) in query_unit.iter_mut() {
+        if let MilitaryBinding::Trajectory { orientation, prev_orientation, .. } = military.bypass_change_detection() {
+            *prev_orientation = *orientation;
+        }
+        let Some(supply) = supply else { continue };
         match military.as_mut() {
             MilitaryBinding::Trajectory {
                 angular_limit, vertical_limit, radius,
-                cooldown, cooldown_timer, orientation, damage, ..
+                cooldown, cooldown_timer, orientation, damage, variant, ..
             } => {
                 if cooldown_timer.duration().is_zero() {
                     cooldown_timer.set_duration(Duration::from_secs_f32(*cooldown * supply.rate_multiplier()));
                 }
-                cooldown_timer.tick(time.delta());
+                cooldown_timer.tick(fixed_time.period);
                 if !cooldown_timer.finished() { continue; }
 
                 let target = target_lock
                     .and_then(|target|query_target.get(**target).ok())
+                    .filter(|(target, _, _)|visibility.is_visible(agent, *target))
                     .filter(|(_, _, transform)|{
                         let distance = transform.translation().distance(transform.translation());
                         radius.0 < distance && distance < radius.1 * supply.range_multipler()
                     })
-                    .or_else(||query_next_target(&lookup, agent, transform, &query_target, radius.0, radius.1 * supply.range_multipler()));
+                    .or_else(||query_next_target(&lookup, &alliances, &visibility, agent, transform, &query_target, radius.0, radius.1 * supply.range_multipler()));
                 let Some((target_entity, _, target_transform)) = target else {
                     commands.entity(entity).remove::<TargetLock>();
                     continue;
@@ -283,7 +381,7 @@ pub fn update_military_targeting(
 
                 let angle = Quat::angle_between(*orientation, next_rotation);
                 if angle >= 1e-3 {
-                    let fraction = angle.min(*angular_limit * time.delta_seconds()) / angle;
+                    let fraction = angle.min(*angular_limit * fixed_time.period.as_secs_f32()) / angle;
                     *orientation = Quat::slerp(*orientation, next_rotation, fraction.min(1.0));
                 } else {
                     cooldown_timer.reset();
@@ -298,20 +396,21 @@ pub fn update_military_targeting(
                             linked: false,
                             intro: Timer::from_seconds(distance * projectile_speed, TimerMode::Once),
                             outro: Timer::from_seconds(1.0 * projectile_speed, TimerMode::Once),
+                            ..Default::default()
                         },
-                        ImpactEffect::Single { interval: Timer::default(), damage: *damage + supply.amplitude }
+                        ImpactEffect::Single { interval: Timer::default(), damage: *damage + supply.amplitude, variant: *variant }
                     )).id();
                     events.send(CombatEvent::ProjectileLaunch(effect, entity, target_entity));
                 }
             },
             MilitaryBinding::Connection {
-                damage, rate, radius, limit, released, degrade
+                damage, variant, rate, radius, limit, released, degrade
             } => {
                 if released >= limit { continue; }
 
                 let Some(
                     (target_entity, _target_agent, _target_transform)
-                ) = query_next_target(&lookup, agent, transform, &query_target, radius.0, radius.1 * supply.range_multipler()) else { continue };
+                ) = query_next_target(&lookup, &alliances, &visibility, agent, transform, &query_target, radius.0, radius.1 * supply.range_multipler()) else { continue };
 
                 *released += 1;
                 commands.spawn((
@@ -323,16 +422,19 @@ pub fn update_military_targeting(
                         linked: true,
                         intro: Timer::from_seconds(1.8, TimerMode::Once),
                         outro: Timer::from_seconds(0.6, TimerMode::Once),
+                        ..Default::default()
                     },
                     ImpactEffect::Single {
                         interval: Timer::from_seconds(*rate * supply.rate_multiplier(), TimerMode::Repeating),
-                        damage: *damage + supply.amplitude
+                        damage: *damage + supply.amplitude,
+                        variant: *variant,
                     }
                 ));
             },
-            MilitaryBinding::Impact { radius, area, damage } => {
+            MilitaryBinding::Impact { radius, area, damage, variant } => {
                 let Some((target_entity, _, target_transform)) = target_lock
-                    .and_then(|target|query_target.get(**target).ok()) else { continue };
+                    .and_then(|target|query_target.get(**target).ok())
+                    .filter(|(target, _, _)|visibility.is_visible(agent, *target)) else { continue };
 
                 let distance_squared = target_transform.translation().distance_squared(transform.translation());
                 if distance_squared > radius.0 * radius.0 { continue; }
@@ -344,6 +446,7 @@ pub fn update_military_targeting(
                     ImpactEffect::Area {
                         interval: Default::default(),
                         damage: *damage + supply.amplitude,
+                        variant: *variant,
                         radius: *area + supply.range_multipler(),
                     }
                 )).id();
@@ -354,18 +457,21 @@ pub fn update_military_targeting(
     }
 }
 
-use super::{MapGrid, GridTileIndex, GroupLink};
-use crate::common::adjacency::breadth_first_search;
+use super::{MapGrid, GridTileIndex, GroupLink, TerrainVariantTable, ArmyReserve};
+use crate::common::adjacency::a_star_search;
 use crate::effects::animation::MovementFormation;
 
 pub fn redirect_unit_directive(
     mut commands: Commands,
     lookup: Res<SpatialLookupGrid<Entity>>,
+    alliances: Res<AllianceTable>,
+    terrain: Res<TerrainVariantTable>,
+    visibility: VisibilityLookup,
     query_grid: Query<(&MapGrid, &GlobalTransform)>,
     query_unit: Query<(
         Entity, &Parent, &Agent, &GridTileIndex, &GlobalTransform, Option<&FollowingPath>,
         &MilitaryBinding, &MilitarySupply, Option<&TargetLock>
-    ), With<MovementFormation>>,
+    ), (With<MovementFormation>, Without<ArmyReserve>)>,
     query_target: Query<(Entity, &Agent, &GlobalTransform), With<Integrity>>,
 ){
     for (
@@ -376,7 +482,7 @@ pub fn redirect_unit_directive(
         let target_position = if let Some(movement) = movement {
             if !movement.stepped_over() || !military.is_close_range() { continue; }
             
-            let target = query_next_target(&lookup, agent, transform, &query_target, 0.0, radius);
+            let target = query_next_target(&lookup, &alliances, &visibility, agent, transform, &query_target, 0.0, radius);
 
             if target_lock.is_some() && target.is_none() { commands.entity(entity).remove::<TargetLock>(); }
             let Some((target_entity, _, target_transform)) = target else { continue };
@@ -390,7 +496,8 @@ pub fn redirect_unit_directive(
             let mut closest = None;
             let mut optimal: f32 = f32::MAX;
             for (target_entity, target_agent, target_transform) in query_target.iter() {
-                if agent.eq(target_agent) { continue; }
+                if !alliances.is_hostile(agent, target_agent) { continue; }
+                if !visibility.is_visible(agent, target_entity) { continue; }
                 let distance_squared = transform.translation().distance_squared(target_transform.translation());
                 if optimal > distance_squared {
                     optimal = distance_squared;
@@ -409,14 +516,13 @@ pub fn redirect_unit_directive(
         let Ok((grid, parent_transform)) = query_grid.get(parent.get()) else { continue };
         let local_target_position = parent_transform.compute_matrix().inverse().transform_point3(target_position);
 
-        let nodes = breadth_first_search(
-            &tile_index.0,
-            |&index|grid.graph.neighbors(index).unwrap().iter()
-            .filter(|&i|
-                grid.tiles[*i].is_empty() || grid.tiles[*i].reference.is_some()
-            ),
-            |index|
-            Some(FloatOrd(grid.tiles[*index].transform.translation.distance_squared(local_target_position)))
+        let target_index = grid.find_closest(tile_index.0, local_target_position);
+        let nodes = a_star_search(
+            &grid.graph, tile_index.0, target_index,
+            |prev, index|if grid.tiles[index].is_empty() || grid.tiles[index].reference.is_some() {
+                Some(grid.movement_cost(&terrain, prev, index))
+            } else { None },
+            |index|grid.tiles[index].transform.translation.distance(local_target_position)
         );
         if let Some(movement) = movement {
             let split = (movement.prev() + 1).min(movement.path.len() - 1);
@@ -436,6 +542,7 @@ pub fn redirect_unit_directive(
 pub fn apply_degradation_effect(
     mut commands: Commands,
     lookup: Res<SpatialLookupGrid<Entity>>,
+    alliances: Res<AllianceTable>,
     query_unit: Query<(Entity, &Agent, &MilitaryBinding, &MilitarySupply, &GlobalTransform)>,
     query_target: Query<(Entity, &Agent, &GlobalTransform), With<Integrity>>,
 ){
@@ -453,7 +560,7 @@ pub fn apply_degradation_effect(
             let distance_squared = position.distance_squared(center);
             if distance_squared < min || distance_squared > max { continue; }
             let Ok((_, target_agent, _)) = query_target.get(entity) else { continue };
-            if agent.eq(target_agent) { continue; }
+            if !alliances.is_hostile(agent, target_agent) { continue; }
 
             if let Some(effect) = degrade {
                 effect.apply(commands.entity(entity));
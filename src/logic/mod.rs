@@ -1,6 +1,7 @@
 mod agent;
 mod group;
 mod event;
+mod clock;
 
 mod foundation;
 mod economy;
@@ -10,10 +11,16 @@ mod fabrication;
 mod movement;
 mod terrain;
 mod strategy;
+mod objective;
+mod visibility;
+mod logistics;
+mod army;
+mod script;
 
 pub use agent::*;
 pub use group::*;
 pub use event::*;
+pub use clock::*;
 
 pub use foundation::*;
 pub use economy::*;
@@ -21,14 +28,19 @@ pub use distribution::*;
 pub use terrain::grid::*;
 pub use terrain::generation::*;
 pub use terrain::lookup::*;
+pub use terrain::variant::*;
 pub use fabrication::*;
 pub use military::*;
 pub use movement::*;
 pub use strategy::*;
+pub use objective::*;
+pub use visibility::*;
+pub use logistics::*;
+pub use army::*;
+pub use script::*;
 
 use bevy::prelude::*;
 use bevy::time::fixed_timestep::run_fixed_update_schedule;
-use bevy::time::common_conditions::on_timer;
 use crate::common::loader::LoadingState;
 use crate::scene::GlobalState;
 
@@ -64,11 +76,21 @@ pub struct LogicPlugin; impl Plugin for LogicPlugin {
 
         app.add_event::<event::ConstructionEvent>();
         app.add_event::<event::CombatEvent>();
+        app.add_event::<event::EconomyEvent>();
+        app.add_event::<event::StageEvent>();
+
+        app.init_resource::<SimulationTick>();
+        app.add_system(clock::reset_simulation_tick.in_schedule(OnExit(GlobalState::Menu)));
+        app.init_resource::<SimulationSpeed>();
+        app.add_system(clock::reset_simulation_speed.in_schedule(OnEnter(GlobalState::Menu)));
+        app.add_system(clock::apply_simulation_speed.in_base_set(CoreSet::PreUpdate));
 
         app.add_systems((
+            clock::advance_simulation_tick,
             movement::execute_structure_relocation,
             movement::execute_probe_landing,
             movement::execute_movement_directives,
+            visibility::update_visibility_phase,
             military::redirect_unit_directive,
             foundation::construction_phase,
             foundation::destruction_phase,
@@ -76,13 +98,14 @@ pub struct LogicPlugin; impl Plugin for LogicPlugin {
         ).chain().in_set(LogicSet::PreFixedUpdate).in_schedule(CoreSchedule::FixedUpdate));
 
         app.add_systems((
-            strategy::evaluate_end_condition,
+            objective::evaluate_end_condition,
             economy::reset_economy_phase,
             economy::production_phase,
             economy::reservation_phase,
             economy::resource_allocation_phase,
             economy::collection_phase,
             foundation::reconstruction_phase,
+            army::restore_reserve_phase,
             fabrication::fabrication_phase,
 
             distribution::expiration_phase,
@@ -94,25 +117,43 @@ pub struct LogicPlugin; impl Plugin for LogicPlugin {
             apply_system_buffers,
             military::resupply_military_phase,
         ).chain().in_set(LogicSet::FixedUpdate).in_schedule(CoreSchedule::FixedUpdate));
+        app.add_system(script::evaluate_stage_triggers
+            .before(objective::evaluate_end_condition)
+            .in_set(LogicSet::FixedUpdate).in_schedule(CoreSchedule::FixedUpdate));
+        app.add_system(economy::report_starvation_phase
+            .after(economy::resource_allocation_phase)
+            .in_set(LogicSet::FixedUpdate).in_schedule(CoreSchedule::FixedUpdate));
+        app.add_system(logistics::unload_carrier_phase
+            .after(economy::collection_phase).before(foundation::reconstruction_phase)
+            .in_set(LogicSet::FixedUpdate).in_schedule(CoreSchedule::FixedUpdate));
 
         app.init_resource::<terrain::lookup::SpatialLookupGrid<Entity>>();
         app.init_resource::<economy::GlobalEconomy>();
+        app.init_resource::<terrain::variant::TerrainVariantTable>();
         app.add_system(terrain::lookup::update_spatial_lookup_grid::<(With<GridTileIndex>, With<Integrity>)>
             .in_base_set(CoreSet::First));
         app.add_systems((
+            apply_system_buffers,
+            military::recharge_barrier_phase,
             military::update_military_targeting,
             military::apply_combat_damage,
-        ).chain().in_set(OnUpdate(LoadingState::Running)));
+        ).chain().after(military::resupply_military_phase)
+        .in_set(LogicSet::FixedUpdate).in_schedule(CoreSchedule::FixedUpdate));
 
         app.add_system(group::relink_network_group.in_set(LogicSet::PreUpdate));
         app.add_system(group::relink_network_group.in_schedule(CoreSchedule::FixedUpdate)
         .after(LogicSet::PreFixedUpdateFlush).before(LogicSet::FixedUpdate));
 
         app.init_resource::<StrategySettings>();
-        app.add_system(strategy::strategical_planning_phase
-            .in_set(LogicSet::PostFixedUpdate)
-            .run_if(on_timer(std::time::Duration::from_secs_f32(5.0)))
-        );
+        app.init_resource::<StageObjectives>();
+        app.init_resource::<StageTriggers>();
+        app.init_resource::<TriggerState>();
+        app.init_resource::<AllianceTable>();
+        app.init_resource::<MatchOutcome>();
+        app.add_systems((
+            strategy::strategical_planning_phase,
+            army::military_planning_phase,
+        ).in_set(LogicSet::PostFixedUpdate));
 
         app.insert_resource(FixedTime::new_from_secs(0.5 * 1.0));
     }
@@ -1,5 +1,5 @@
 use bevy::prelude::*;
-use super::{MapGrid, GridTileIndex, GroupLink, ConstructionEvent, Integrity, DegradeImmobilize, MilitarySupply};
+use super::{MapGrid, GridTileIndex, GroupLink, ConstructionEvent, Integrity, DegradeImmobilize, MilitarySupply, TerrainVariantTable};
 use crate::effects::animation::MovementFormation;
 
 #[derive(Component, serde::Deserialize, Deref, DerefMut, Clone, Default, Debug)]
@@ -50,10 +50,12 @@ impl FollowingPath {
 
 pub fn execute_movement_directives(
     mut commands: Commands,
-    mut query_unit: Query<(Entity, &mut FollowingPath, &mut GridTileIndex, &Velocity, Option<&DegradeImmobilize>), With<MovementFormation>>,
+    terrain: Res<TerrainVariantTable>,
+    query_grid: Query<&MapGrid>,
+    mut query_unit: Query<(Entity, &Parent, &mut FollowingPath, &mut GridTileIndex, &Velocity, Option<&DegradeImmobilize>), With<MovementFormation>>,
 ){
     for (
-        entity, mut movement, mut tile_index, velocity, immobilize
+        entity, parent, mut movement, mut tile_index, velocity, immobilize
     ) in query_unit.iter_mut() {
         if movement.stepped_over() || movement.just_started() {
             movement.last_step = 0;
@@ -67,7 +69,9 @@ pub fn execute_movement_directives(
         if let Some(immobilize) = immobilize {
             velocity *= 1 + immobilize.0;
         }
-        movement.update(velocity as f32);
+        let movement_cost = query_grid.get(parent.get())
+            .map_or(1.0, |grid|terrain.movement(grid.tiles[tile_index.0].variant));
+        movement.update(velocity as f32 * movement_cost);
     }
 }
 
@@ -92,7 +96,7 @@ pub fn execute_probe_landing(
 
             events.send(ConstructionEvent::Assemble { entity, parent: parent.get(), index: **tile_index, extend: false });
         } else {
-            integrity.apply_damage(i32::MAX);
+            integrity.destroy();
         }
     }
 }
@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use crate::logic::{Agent, AllianceTable, MapGrid, MatterBinding, ResourceVariant, SimulationTick, TriggerState};
+use crate::scene::GlobalState;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub enum ObjectiveCondition {
+    Survive { minutes: f32 },
+    ///Index of the unit placement in the stage blueprint.
+    Destroy { unit: usize },
+    Hold { tiles: usize },
+    Accumulate { matter: i32 },
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ObjectiveOutcome {
+    Victory,
+    Defeat,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct Objective {
+    pub agent: Agent,
+    pub condition: ObjectiveCondition,
+    pub outcome: ObjectiveOutcome,
+}
+
+///Stage specific goals, checked in addition to elimination of all agent units.
+///Victory of one agent is shared with its allies.
+#[derive(Resource, serde::Serialize, serde::Deserialize, Deref, Clone, Default, Debug)]
+pub struct StageObjectives(pub Vec<Objective>);
+
+#[derive(Component, Deref, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct StagePlacement(pub usize);
+
+#[derive(Resource, serde::Serialize, serde::Deserialize, Clone, Default, Debug)]
+pub struct MatchOutcome {
+    pub agents: Vec<(Agent, Option<ObjectiveOutcome>)>,
+}
+impl MatchOutcome {
+    pub fn get(&self, agent: Agent) -> Option<ObjectiveOutcome> {
+        self.agents.iter().find(|(key, _)|*key == agent).and_then(|(_, outcome)|*outcome)
+    }
+    pub fn decide(&mut self, agent: Agent, outcome: ObjectiveOutcome){
+        match self.agents.iter_mut().find(|(key, _)|*key == agent) {
+            Some(entry) => if entry.1.is_none() { entry.1 = Some(outcome); },
+            None => self.agents.push((agent, Some(outcome))),
+        }
+    }
+    fn undecided(&self) -> impl Iterator<Item = Agent> + '_ {
+        self.agents.iter().filter(|(_, outcome)|outcome.is_none()).map(|(agent, _)|*agent)
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct AgentSummary {
+    units: usize,
+    tiles: usize,
+    matter: i32,
+}
+
+pub fn evaluate_end_condition(
+    tick: Res<SimulationTick>,
+    fixed_time: Res<FixedTime>,
+    objectives: Res<StageObjectives>,
+    alliances: Res<AllianceTable>,
+    triggers: Res<TriggerState>,
+    mut outcome: ResMut<MatchOutcome>,
+    mut next_state: ResMut<NextState<GlobalState>>,
+    query_grid: Query<&MapGrid>,
+    query_unit: Query<(&Agent, Option<&StagePlacement>, Option<&MatterBinding>)>,
+){
+    let mut summary: Vec<(Agent, AgentSummary)> = outcome.agents.iter()
+        .map(|(agent, _)|(*agent, AgentSummary::default())).collect();
+    let mut placements: Vec<usize> = Vec::new();
+    for (agent, placement, matter) in query_unit.iter() {
+        let index = summary.iter().position(|(key, _)|key == agent).unwrap_or_else(||{
+            summary.push((*agent, AgentSummary::default()));
+            summary.len() - 1
+        });
+        let entry = &mut summary[index].1;
+        entry.units += 1;
+        if let Some(MatterBinding::Collection(storage)) = matter {
+            if storage.resource == ResourceVariant::Matter { entry.matter += storage.stored; }
+        }
+        if let Some(placement) = placement { placements.push(**placement); }
+    }
+    for grid in query_grid.iter() {
+        for tile in grid.tiles.iter().filter(|tile|tile.flags & MapGrid::OWNERSHIP != 0) {
+            let Some((agent, _, _)) = tile.reference.and_then(|entity|query_unit.get(entity).ok()) else { continue };
+            let Some((_, entry)) = summary.iter_mut().find(|(key, _)|key == agent) else { continue };
+            entry.tiles += 1;
+        }
+    }
+    for (agent, _) in summary.iter() {
+        if outcome.agents.iter().all(|(key, _)|key != agent) { outcome.agents.push((*agent, None)); }
+    }
+
+    let elapsed = fixed_time.period.as_secs_f32() * tick.elapsed as f32;
+    for (agent, entry) in summary.iter() {
+        if entry.units == 0 { outcome.decide(*agent, ObjectiveOutcome::Defeat); }
+        for objective in objectives.iter().filter(|objective|objective.agent == *agent) {
+            if match objective.condition {
+                ObjectiveCondition::Survive { minutes } => elapsed >= minutes * 60.0,
+                ObjectiveCondition::Destroy { unit } => triggers.spawned.contains(&unit) && !placements.contains(&unit),
+                ObjectiveCondition::Hold { tiles } => entry.tiles >= tiles,
+                ObjectiveCondition::Accumulate { matter } => entry.matter >= matter,
+            } {
+                outcome.decide(*agent, objective.outcome);
+            }
+        }
+    }
+
+    let victors: Vec<Agent> = outcome.agents.iter()
+        .filter(|(_, outcome)|*outcome == Some(ObjectiveOutcome::Victory))
+        .map(|(agent, _)|*agent).collect();
+    let undecided: Vec<Agent> = outcome.undecided()
+        .filter(|agent|!alliances.is_neutral(agent)).collect();
+    if !victors.is_empty() {
+        for agent in undecided {
+            outcome.decide(agent, if victors.iter().any(|victor|alliances.is_allied(victor, &agent)) {
+                ObjectiveOutcome::Victory
+            } else {
+                ObjectiveOutcome::Defeat
+            });
+        }
+    } else if let Some(first) = undecided.first() {
+        let remaining = undecided.iter().all(|agent|alliances.is_allied(first, agent));
+        let opposed = outcome.agents.iter().any(|(agent, _)|
+            !alliances.is_neutral(agent) && !alliances.is_allied(first, agent));
+        if remaining && opposed {
+            for agent in undecided { outcome.decide(agent, ObjectiveOutcome::Victory); }
+        }
+    }
+
+    if outcome.get(Agent::Player).is_some() || outcome.undecided().all(|agent|alliances.is_neutral(&agent)) {
+        next_state.set(GlobalState::Menu);
+    }
+}
@@ -0,0 +1,255 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use crate::common::loader::AssetBundle;
+use crate::common::adjacency::TraversableGraph;
+use crate::scene::{UnitPlacement, UnitBlueprint, BlueprintAssetBundle, ModelAssetBundle};
+use crate::interaction::construct_structure;
+use crate::logic::{Agent, MapGrid, GridAreaIndex, GridVisibility, MatterBinding, ResourceVariant, SimulationTick};
+use crate::logic::{StrategySettings, StrategyProfile, StagePlacement, MatchOutcome, ObjectiveOutcome, ConstructionEvent, StageEvent};
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub enum TriggerCondition {
+    Elapsed { seconds: f32 },
+    ///Index of the unit placement in the stage blueprint.
+    Destroyed { unit: usize },
+    Captured { agent: Agent, area: usize, tile: usize },
+    Matter { agent: Agent, amount: i32 },
+    ///Index of another trigger in the stage blueprint.
+    Fired { trigger: usize },
+    All(Vec<TriggerCondition>),
+    Any(Vec<TriggerCondition>),
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub enum TriggerAction {
+    Spawn(Vec<UnitPlacement>),
+    Message(String),
+    Strategy { agent: u8, profile: StrategyProfile },
+    Reveal { agent: Agent, area: usize, tile: usize, radius: usize },
+    End { agent: Agent, outcome: ObjectiveOutcome },
+}
+
+///Scripted actions executed once when the condition is first met.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct StageTrigger {
+    pub condition: TriggerCondition,
+    pub actions: Vec<TriggerAction>,
+}
+impl StageTrigger {
+    ///Units placed by the spawn actions of the trigger.
+    pub fn spawn_count(&self) -> usize {
+        self.actions.iter().map(|action|match action {
+            TriggerAction::Spawn(placements) => placements.len(),
+            _ => 0
+        }).sum()
+    }
+}
+
+#[derive(Resource, serde::Serialize, serde::Deserialize, Deref, Clone, Default)]
+pub struct StageTriggers(pub Vec<StageTrigger>);
+impl StageTriggers {
+    ///Units spawned by triggers follow the stage placements, in trigger and action order.
+    pub fn spawn_count(&self) -> usize { self.iter().map(StageTrigger::spawn_count).sum() }
+    pub fn validate(&self, placements: usize) -> Result<(), String> {
+        for trigger in self.iter() {
+            trigger.condition.validate(placements, self.len())?;
+            for action in trigger.actions.iter() { match action {
+                TriggerAction::Spawn(placements) => if let Some(placement) = placements.iter().find(|placement|!placement.agent.is_valid()) {
+                    return Err(format!("invalid agent {:?}", placement.agent));
+                },
+                &TriggerAction::Strategy { agent, .. } => if !Agent::AI(agent).is_valid() {
+                    return Err(format!("invalid agent {:?}", Agent::AI(agent)));
+                },
+                TriggerAction::Reveal { agent, .. } | TriggerAction::End { agent, .. } => if !agent.is_valid() {
+                    return Err(format!("invalid agent {:?}", agent));
+                },
+                TriggerAction::Message(_) => {},
+            } }
+        }
+        Ok(())
+    }
+}
+
+///Indices of the stage triggers which already fired.
+#[derive(Resource, serde::Serialize, serde::Deserialize, Clone, Default, Debug)]
+pub struct TriggerState {
+    pub fired: Vec<usize>,
+    ///Placement index of the first unit spawned by triggers.
+    #[serde(default)] pub spawn_offset: usize,
+    ///Placement indices of units which were present at some point.
+    #[serde(default)] pub spawned: Vec<usize>,
+}
+
+struct TriggerContext {
+    elapsed: f32,
+    placements: Vec<usize>,
+    matter: HashMap<Agent, i32>,
+    owners: HashMap<(usize, usize), Agent>,
+}
+impl TriggerCondition {
+    fn validate(&self, placements: usize, triggers: usize) -> Result<(), String> { match self {
+        TriggerCondition::Captured { agent, .. } |
+        TriggerCondition::Matter { agent, .. } if !agent.is_valid() => Err(format!("invalid agent {:?}", agent)),
+        &TriggerCondition::Destroyed { unit } if unit >= placements => Err(format!("invalid placement {}", unit)),
+        &TriggerCondition::Fired { trigger } if trigger >= triggers => Err(format!("invalid trigger {}", trigger)),
+        TriggerCondition::All(conditions) |
+        TriggerCondition::Any(conditions) => conditions.iter()
+            .try_for_each(|condition|condition.validate(placements, triggers)),
+        _ => Ok(())
+    } }
+    ///Updates placement indices, false when the condition depends on a removed placement.
+    pub fn remap_placements(&mut self, remap: &impl Fn(usize) -> Option<usize>) -> bool { match self {
+        TriggerCondition::Destroyed { unit } => remap(*unit).map(|next|*unit = next).is_some(),
+        TriggerCondition::All(conditions) => conditions.iter_mut()
+            .fold(true, |valid, condition|condition.remap_placements(remap) && valid),
+        TriggerCondition::Any(conditions) => {
+            conditions.retain_mut(|condition|condition.remap_placements(remap));
+            !conditions.is_empty()
+        },
+        _ => true
+    } }
+    fn evaluate(&self, context: &TriggerContext, state: &TriggerState) -> bool { match self {
+        TriggerCondition::Elapsed { seconds } => context.elapsed >= *seconds,
+        TriggerCondition::Destroyed { unit } => state.spawned.contains(unit) && !context.placements.contains(unit),
+        TriggerCondition::Captured { agent, area, tile } => context.owners.get(&(*area, *tile)) == Some(agent),
+        TriggerCondition::Matter { agent, amount } => context.matter.get(agent).map_or(false, |matter|matter >= amount),
+        TriggerCondition::Fired { trigger } => state.fired.contains(trigger),
+        TriggerCondition::All(conditions) => conditions.iter().all(|condition|condition.evaluate(context, state)),
+        TriggerCondition::Any(conditions) => conditions.iter().any(|condition|condition.evaluate(context, state)),
+    } }
+}
+
+pub fn evaluate_stage_triggers(
+    tick: Res<SimulationTick>,
+    fixed_time: Res<FixedTime>,
+    triggers: Res<StageTriggers>,
+    mut state: ResMut<TriggerState>,
+    mut settings: ResMut<StrategySettings>,
+    mut outcome: ResMut<MatchOutcome>,
+    mut commands: Commands,
+    mut events: EventWriter<StageEvent>,
+    mut construction_events: EventWriter<ConstructionEvent>,
+    blueprint_bundle: Res<AssetBundle<BlueprintAssetBundle>>,
+    model_bundle: Option<Res<AssetBundle<ModelAssetBundle>>>,
+    blueprints: Res<Assets<UnitBlueprint>>,
+    mut query_grid: Query<(Entity, &GridAreaIndex, &mut MapGrid, &mut GridVisibility)>,
+    query_unit: Query<(&Agent, Option<&StagePlacement>, Option<&MatterBinding>)>,
+){
+    let mut context = TriggerContext {
+        elapsed: fixed_time.period.as_secs_f32() * tick.elapsed as f32,
+        placements: Vec::new(),
+        matter: HashMap::default(),
+        owners: HashMap::default(),
+    };
+    for (agent, placement, matter) in query_unit.iter() {
+        if let Some(placement) = placement { context.placements.push(**placement); }
+        if let Some(MatterBinding::Collection(storage)) = matter {
+            if storage.resource == ResourceVariant::Matter { *context.matter.entry(*agent).or_default() += storage.stored; }
+        }
+    }
+    for &placement in context.placements.iter() {
+        if !state.spawned.contains(&placement) { state.spawned.push(placement); }
+    }
+    if triggers.len() == state.fired.len() { return; }
+    for (_, area_index, grid, _) in query_grid.iter() {
+        for (index, tile) in grid.tiles.iter().enumerate().filter(|(_, tile)|tile.flags & MapGrid::OWNERSHIP != 0) {
+            let Some((agent, _, _)) = tile.reference.and_then(|entity|query_unit.get(entity).ok()) else { continue };
+            context.owners.insert((**area_index, index), *agent);
+        }
+    }
+
+    let mut placement_index = state.spawn_offset;
+    for (trigger_index, trigger) in triggers.iter().enumerate() {
+        if state.fired.contains(&trigger_index) || !trigger.condition.evaluate(&context, &state) {
+            placement_index += trigger.spawn_count();
+            continue;
+        }
+        state.fired.push(trigger_index);
+        for action in trigger.actions.iter() { match action {
+            TriggerAction::Spawn(placements) => for placement in placements.iter() {
+                let index = placement_index;
+                placement_index += 1;
+                let Some((parent, _, mut grid, _)) = query_grid.iter_mut()
+                    .find(|(_, area_index, _, _)|***area_index == placement.area) else { continue };
+                if grid.tiles[placement.tile].reference.is_some() { continue; }
+                let handle = blueprint_bundle.find_unit(&placement.key);
+                let entity = construct_structure(
+                    &mut commands, &mut construction_events, parent, &mut grid, model_bundle.as_deref(), &blueprints,
+                    (handle.clone(), placement.agent, placement.tile, 0), true
+                );
+                commands.entity(entity).insert(StagePlacement(index));
+            },
+            TriggerAction::Message(text) => events.send(StageEvent::Message(text.clone())),
+            TriggerAction::Strategy { agent, profile } => {
+                settings.profiles.insert(*agent, *profile);
+            },
+            TriggerAction::Reveal { agent, area, tile, radius } => {
+                let Some((_, _, grid, mut visibility)) = query_grid.iter_mut()
+                    .find(|(_, area_index, _, _)|***area_index == *area) else { continue };
+                visibility.revealed.resize(grid.tiles.len(), 0);
+                for index in grid.graph.iter_breadth_first().with_origin(*tile).with_limit(*radius) {
+                    visibility.revealed[index] |= agent.mask();
+                }
+            },
+            TriggerAction::End { agent, outcome: result } => outcome.decide(*agent, *result),
+        } }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> TriggerContext {
+        TriggerContext {
+            elapsed: 30.0,
+            placements: vec![0, 2],
+            matter: [(Agent::Player, 40), (Agent::AI(1), 5)].into_iter().collect(),
+            owners: HashMap::default(),
+        }
+    }
+
+    #[test]
+    pub fn evaluate_elapsed_and_matter(){
+        let (context, state) = (context(), TriggerState::default());
+        assert!(TriggerCondition::Elapsed { seconds: 30.0 }.evaluate(&context, &state));
+        assert!(!TriggerCondition::Elapsed { seconds: 30.5 }.evaluate(&context, &state));
+        assert!(TriggerCondition::Matter { agent: Agent::Player, amount: 40 }.evaluate(&context, &state));
+        assert!(!TriggerCondition::Matter { agent: Agent::AI(1), amount: 10 }.evaluate(&context, &state));
+        assert!(!TriggerCondition::Matter { agent: Agent::AI(2), amount: 0 }.evaluate(&context, &state));
+    }
+
+    #[test]
+    pub fn evaluate_destroyed_requires_spawned(){
+        let context = context();
+        let state = TriggerState { spawned: vec![0, 1, 2], ..Default::default() };
+        assert!(TriggerCondition::Destroyed { unit: 1 }.evaluate(&context, &state));
+        assert!(!TriggerCondition::Destroyed { unit: 2 }.evaluate(&context, &state));
+        assert!(!TriggerCondition::Destroyed { unit: 3 }.evaluate(&context, &state));
+    }
+
+    #[test]
+    pub fn evaluate_composite_and_fired(){
+        let context = context();
+        let state = TriggerState { fired: vec![1], ..Default::default() };
+        let elapsed = ||TriggerCondition::Elapsed { seconds: 10.0 };
+        let pending = ||TriggerCondition::Fired { trigger: 0 };
+        assert!(TriggerCondition::Fired { trigger: 1 }.evaluate(&context, &state));
+        assert!(!pending().evaluate(&context, &state));
+        assert!(TriggerCondition::All(vec![elapsed(), TriggerCondition::Fired { trigger: 1 }]).evaluate(&context, &state));
+        assert!(!TriggerCondition::All(vec![elapsed(), pending()]).evaluate(&context, &state));
+        assert!(TriggerCondition::Any(vec![pending(), elapsed()]).evaluate(&context, &state));
+        assert!(!TriggerCondition::Any(vec![pending()]).evaluate(&context, &state));
+        assert!(TriggerCondition::All(Vec::new()).evaluate(&context, &state));
+        assert!(!TriggerCondition::Any(Vec::new()).evaluate(&context, &state));
+    }
+
+    #[test]
+    pub fn validate_rejects_invalid_references(){
+        let trigger = |condition|StageTriggers(vec![StageTrigger { condition, actions: Vec::new() }]);
+        assert!(trigger(TriggerCondition::Destroyed { unit: 2 }).validate(3).is_ok());
+        assert!(trigger(TriggerCondition::Destroyed { unit: 3 }).validate(3).is_err());
+        assert!(trigger(TriggerCondition::Any(vec![TriggerCondition::Fired { trigger: 1 }])).validate(3).is_err());
+        assert!(trigger(TriggerCondition::Matter { agent: Agent::AI(31), amount: 0 }).validate(3).is_err());
+    }
+}
@@ -1,34 +1,17 @@
 use bevy::prelude::*;
-use bevy::utils::{HashSet, FloatOrd};
+use bevy::utils::HashSet;
 use crate::common::loader::AssetBundle;
-use crate::common::noise::{MurMurHash, WeightTable};
-use crate::common::adjacency::breadth_first_search;
-use crate::logic::{Agent, GridTileIndex, MapGrid, GroupLink, NetworkGroupList, EconomySummary};
+use crate::common::noise::WeightTable;
+use crate::common::adjacency::{dijkstra_distance_field, DistanceField};
+use crate::logic::{Agent, AllianceTable, MapGrid, NetworkGroupList, EconomySummary, SimulationTick};
+use crate::logic::{ResourceVariant, ResourceAmount, TerrainVariantTable, GridVisibility};
 use crate::logic::{UnderConstruction, Suspended, MatterBinding, FabricationGate, UnitDirective};
-use crate::scene::{UnitBlueprint, BlueprintAssetBundle, GlobalState};
-use crate::interaction::{InteractionEvent, ActionSelector, ViewMode, path::ActionPath};
+use crate::scene::{UnitBlueprint, BlueprintAssetBundle};
+use crate::interaction::{InteractionEvent, ActionSelector, path::ActionPath};
 use crate::interface::construct::validate_construction;
 
-pub fn evaluate_end_condition(
-    query: Query<&Agent>,
-    mut mode: ResMut<ViewMode>,
-    mut next_state: ResMut<NextState<GlobalState>>,
-){
-    let mut mask: u8 = 0;
-    for agent in query.iter() {
-        mask |= match agent {
-            Agent::Player => 0x1,
-            &Agent::AI(agent_index) => 1 << agent_index
-        };
-    }
-    if mask == 0x1 || mask & 0x1 == 0 {
-        next_state.set(GlobalState::Menu);
-        *mode = ViewMode::Menu;
-    }
-}
-
 pub struct HeuristicContext {
-    any_construction: bool,
+    any_queued: bool,
     any_gate: bool,
 }
 
@@ -41,42 +24,59 @@ pub enum Heuristic {
     Civilian(i32)
 }
 impl Heuristic {
-    pub fn from_toggle(blueprint: &UnitBlueprint, _settings: &StrategySettings, summary: &EconomySummary, toggle: bool) -> Self {
-        let delta = match blueprint.matter.as_ref() {
-            Some(MatterBinding::Consumption(consumption)) => -consumption.quota,
-            Some(MatterBinding::Production(production)) => production.efficiency,
-            _ => 0
+    ///Expected resource change, matter production is scaled by the remaining deposit of the tile.
+    fn resource_delta(blueprint: &UnitBlueprint, deposit: f32) -> ResourceAmount {
+        let mut delta = ResourceAmount::default();
+        match blueprint.matter.as_ref() {
+            Some(MatterBinding::Consumption(consumption)) => for (resource, quota) in consumption.requirements() {
+                delta[resource] -= quota;
+            },
+            Some(MatterBinding::Production(production)) => delta[production.resource] += match production.resource {
+                ResourceVariant::Matter => (production.efficiency as f32 * deposit).round() as i32,
+                _ => production.efficiency,
+            },
+            _ => {}
         };
-        let prev_delta = summary.matter_production - summary.matter_consumption;
-        let next_delta = prev_delta + if toggle { delta }else{ -delta };
+        delta
+    }
+    pub fn from_toggle(blueprint: &UnitBlueprint, _settings: &StrategySettings, summary: &EconomySummary, toggle: bool, deposit: f32) -> Self {
+        let delta = Self::resource_delta(blueprint, deposit);
+        let mut improvement = 0;
+        let mut deficit = false;
+        for resource in ResourceVariant::ALL {
+            let prev_delta = summary[resource].delta();
+            let next_delta = prev_delta + if toggle { delta[resource] }else{ -delta[resource] };
+            if prev_delta <= 0 && next_delta > prev_delta { improvement += next_delta - prev_delta; }
+            if next_delta < 0 && next_delta < prev_delta { deficit = true; }
+        }
 
-        if prev_delta <= 0 && next_delta > prev_delta {
-            Heuristic::Economy(next_delta - prev_delta)
-        } else if toggle && next_delta > 0 {
+        if improvement > 0 && !deficit {
+            Heuristic::Economy(improvement)
+        } else if toggle && !deficit && summary[ResourceVariant::Matter].delta() + delta[ResourceVariant::Matter] > 0 {
             Heuristic::Neutral
         } else {
             Heuristic::Disabled
         }
     }
-    pub fn from_construct(blueprint: &UnitBlueprint, settings: &StrategySettings, summary: &EconomySummary, context: &HeuristicContext) -> Self {
-        let delta = match blueprint.matter.as_ref() {
-            Some(MatterBinding::Consumption(consumption)) => -consumption.quota,
-            Some(MatterBinding::Production(production)) => production.efficiency,
-            Some(MatterBinding::Collection(storage)) => 0 * storage.discharge,
-            None => 0
-        };
-        let prev_delta = summary.matter_production - summary.matter_consumption;
-        let prev_storage = summary.matter_reservation + prev_delta;
-        let next_delta = prev_delta + delta;
-        if context.any_construction || prev_delta <= 0 && prev_storage <= 0 || next_delta < 0 {
+    pub fn from_construct(blueprint: &UnitBlueprint, settings: &StrategySettings, summary: &EconomySummary, context: &HeuristicContext, deposit: f32) -> Self {
+        let delta = Self::resource_delta(blueprint, deposit);
+        let matter = &summary[ResourceVariant::Matter];
+        let prev_delta = matter.delta();
+        let prev_storage = matter.reservation + prev_delta;
+        let deficit = ResourceVariant::ALL.iter().any(|&resource|summary[resource].delta() + delta[resource] < 0);
+        if context.any_queued || prev_delta <= 0 && prev_storage <= 0 || deficit {
             Heuristic::Disabled
         } else {
             let military = 2 * (blueprint.military.is_some() as i32) +
             blueprint.unit.as_ref().map_or(0, |fabrication|if fabrication.group == 1 { 1 }else{ 0 });
             let upgrade = blueprint.upgrade.is_some() as i32;
+            let improvement: i32 = ResourceVariant::ALL.iter().map(|&resource|{
+                let threshold = if resource == ResourceVariant::Matter { settings.low_matter_threshold }else{ 0 };
+                if summary[resource].delta() < threshold { delta[resource].max(0) }else{ 0 }
+            }).sum();
 
-            if prev_delta < settings.low_matter_threshold && next_delta > prev_delta {
-                Heuristic::Economy(next_delta - prev_delta)
+            if improvement > 0 {
+                Heuristic::Economy(improvement)
             } else if prev_delta >= settings.low_matter_threshold && military > 0 {
                 Heuristic::Military(military)
             } else if prev_delta >= settings.low_matter_threshold && upgrade > 0 {
@@ -86,53 +86,131 @@ impl Heuristic {
             }
         }
     }
-    pub fn from_action(_blueprint: &UnitBlueprint, settings: &StrategySettings, summary: &EconomySummary, context: &HeuristicContext) -> Self {
-        let matter_delta = summary.matter_production - summary.matter_consumption;
+    pub fn from_action(_blueprint: &UnitBlueprint, settings: &StrategySettings, profile: &StrategyProfile, summary: &EconomySummary, context: &HeuristicContext) -> Self {
+        let matter_delta = summary[ResourceVariant::Matter].delta();
         if context.any_gate {
             Heuristic::Disabled
-        } else if matter_delta > settings.low_matter_threshold {
+        } else if matter_delta > profile.aggression.unwrap_or(settings.low_matter_threshold) {
             Heuristic::Military(1)
         } else {
             Heuristic::Neutral
         }
     }
-    pub fn weight(&self) -> i32 { match self {
-        Heuristic::Disabled => -1,
-        Heuristic::Neutral => 0,
-        Heuristic::Economy(score) | Heuristic::Military(score) | Heuristic::Civilian(score) => *score
+    ///Selection weight scaled by the profile, candidates without positive weight are never picked.
+    pub fn weight(&self, profile: &StrategyProfile) -> f32 { match self {
+        Heuristic::Disabled => 0.0,
+        Heuristic::Neutral => 1.0,
+        Heuristic::Economy(score) => (*score + 1) as f32 * profile.economy,
+        Heuristic::Military(score) => (*score + 1) as f32 * profile.military,
+        Heuristic::Civilian(score) => (*score + 1) as f32 * profile.civilian,
     } }
 }
 
-#[derive(Resource, serde::Deserialize, Clone, Default)]
+///Difficulty and personality of an AI agent.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct StrategyProfile {
+    ///Simulation ticks between decisions.
+    pub interval: u64,
+    ///Matter granted to each group every tick.
+    pub income: i32,
+    pub economy: f32,
+    pub military: f32,
+    pub civilian: f32,
+    ///Matter surplus required to open gates, defaults to low matter threshold.
+    pub aggression: Option<i32>,
+    ///Tiles away from the network where released units gather in reserve.
+    pub rally_distance: usize,
+    ///Units held in reserve before launching an attack wave.
+    pub wave_size: usize,
+    ///Radius around structures watched for hostile units.
+    pub threat_radius: f32,
+    ///Integrity fraction below which units retreat to the reserve.
+    pub retreat_threshold: f32,
+    ///Tiles relocatable structures advance towards the front per decision.
+    pub advance_distance: usize,
+}
+impl Default for StrategyProfile {
+    fn default() -> Self { Self {
+        interval: 10, income: 0,
+        economy: 1.0, military: 1.0, civilian: 1.0,
+        aggression: None,
+        rally_distance: 3, wave_size: 6,
+        threat_radius: 8.0, retreat_threshold: 0.3,
+        advance_distance: 2,
+    } }
+}
+
+#[derive(Resource, serde::Serialize, serde::Deserialize, Clone, Default)]
 pub struct StrategySettings {
     pub low_matter_threshold: i32,
+    ///Profiles of AI agents by index, agents not listed use the default profile.
+    #[serde(default)] pub profiles: std::collections::HashMap<u8, StrategyProfile>,
+}
+impl StrategySettings {
+    pub fn profile(&self, agent: &Agent) -> Option<StrategyProfile> { match agent {
+        Agent::AI(index) => Some(self.profiles.get(index).copied().unwrap_or_default()),
+        Agent::Player => None,
+    } }
+}
+
+///Distance to visible hostile structures, or to unexplored tiles when none are in sight.
+///Once the whole area is explored, hostile structures are targeted where they were last seen.
+pub fn hostile_distance_field(
+    alliances: &AllianceTable,
+    terrain: &TerrainVariantTable,
+    agent: &Agent,
+    grid: &MapGrid,
+    visibility: Option<&GridVisibility>,
+) -> DistanceField<usize> {
+    let mask = alliances.vision_mask(agent);
+    let sighted: Vec<usize> = (0..grid.tiles.len())
+    .filter(|&index|visibility.map_or(false, |visibility|visibility.is_sighted(index, agent))).collect();
+    let mut sources: Vec<usize> = sighted.iter().copied()
+    .filter(|&index|visibility.map_or(false, |visibility|visibility.is_visible(index, mask))).collect();
+    if sources.is_empty() {
+        sources.extend((0..grid.tiles.len())
+        .filter(|&index|!visibility.map_or(false, |visibility|visibility.is_explored(index, mask))));
+    }
+    if sources.is_empty() {
+        sources.extend(sighted);
+    }
+    dijkstra_distance_field(
+        &grid.graph, sources,
+        |prev, index|if grid.tiles[index].is_empty() || grid.tiles[index].reference.is_some() {
+            Some(grid.movement_cost(terrain, prev, index))
+        } else { None },
+        None
+    )
 }
 
 pub fn strategical_planning_phase(
-    time: Res<Time>,
+    tick: Res<SimulationTick>,
     settings: Res<StrategySettings>,
-    mut rng: Local<MurMurHash>,
+    alliances: Res<AllianceTable>,
+    terrain: Res<TerrainVariantTable>,
     mut events: EventWriter<InteractionEvent>,
     blueprints: Res<Assets<UnitBlueprint>>,
     blueprint_bundle: Res<AssetBundle<BlueprintAssetBundle>>,
-    query_grid: Query<(Entity, &MapGrid, &NetworkGroupList)>,
+    query_grid: Query<(Entity, &MapGrid, &NetworkGroupList, Option<&GridVisibility>)>,
     query_unit: Query<(
         &Handle<UnitBlueprint>, Option<&UnderConstruction>, Option<&Suspended>, Option<&FabricationGate>
     )>,
-    query_target: Query<(Entity, &Agent, &GridTileIndex), With<GroupLink>>,
 ){
-    for (parent, grid, groups) in query_grid.iter() {
+    let mut rng = tick.random();
+    for (parent, grid, groups, visibility) in query_grid.iter() {
         for group in groups.iter() {
-            let Agent::AI(_agent_index) = group.agent else { continue };
+            let Some(profile) = settings.profile(&group.agent) else { continue };
+            if tick.elapsed % profile.interval.max(1) != 0 { continue; }
             let context = HeuristicContext {
-                any_construction: group.list.iter()
-                .any(|item|query_unit.get_component::<UnderConstruction>(item.1).is_ok()),
+                any_queued: !group.queue.is_empty(),
                 any_gate: group.list.iter()
                 .any(|item|query_unit.get_component::<FabricationGate>(item.1).is_ok()),
             };
             
             let mut candidates: Vec<(Heuristic, InteractionEvent)> = Vec::new();
             let mut visited: HashSet<usize> = Default::default();
+            let mut hostile_field: Option<DistanceField<usize>> = None;
             for &(index, entity) in group.list.iter() {
                 let Ok((
                     handle, construction, suspended, gate,
@@ -141,47 +219,30 @@ pub fn strategical_planning_phase(
                 if construction.is_some() { continue; }
 
                 candidates.push((
-                    Heuristic::from_toggle(blueprint, &settings, &group.summary, suspended.is_some()),
+                    Heuristic::from_toggle(blueprint, &settings, &group.summary, suspended.is_some(), grid.tiles[index].deposit()),
                     InteractionEvent::Toggle(entity)
                 ));
 
                 for handle in blueprint_bundle.unit_blueprints.iter() {
                     let Some(next_blueprint) = blueprints.get(handle) else { continue };
                     if !validate_construction(
-                        next_blueprint, Some(blueprint), &group.agent, grid, groups, index
+                        next_blueprint, Some(blueprint), &group.agent, &alliances, &terrain, grid, groups, index
                     ) { continue; }
 
                     candidates.push((
-                        Heuristic::from_construct(next_blueprint, &settings, &group.summary, &context),
+                        Heuristic::from_construct(next_blueprint, &settings, &group.summary, &context, grid.tiles[index].deposit()),
                         InteractionEvent::Construct(group.agent, parent, index, handle.clone())
                     ));
                 }
 
                 if let Some(UnitDirective::OpenGate) = blueprint.action {
-                    let mut min_distance_squared = f32::MAX;
-                    let mut min_index = index;
-                    for (_entity, agent, tile_index) in query_target.iter() {
-                        if group.agent.eq(agent) { continue; }
-                        let distance_squared = grid.tiles[tile_index.0].transform.translation
-                        .distance_squared(grid.tiles[index].transform.translation);
-                        if distance_squared < min_distance_squared {
-                            min_distance_squared = distance_squared;
-                            min_index = tile_index.0;
-                        }
-                    }
-                    if min_index != index {
-                        let target_position = grid.tiles[min_index].transform.translation;
-                        let nodes = breadth_first_search(
-                            &index,
-                            |&index|grid.graph.neighbors(index).unwrap().iter()
-                            .filter(|&i|
-                                grid.tiles[*i].is_empty() || grid.tiles[*i].reference.is_some()
-                            ),
-                            |index|
-                            Some(FloatOrd(grid.tiles[*index].transform.translation.distance_squared(target_position)))
-                        );
+                    let field = hostile_field.get_or_insert_with(||hostile_distance_field(
+                        &alliances, &terrain, &group.agent, grid, visibility
+                    ));
+                    if let Some(mut nodes) = field.path_from(index).filter(|nodes|nodes.len() > 1) {
+                        nodes.truncate(profile.rally_distance + 1);
                         candidates.push((
-                            Heuristic::from_action(blueprint, &settings, &group.summary, &context),
+                            Heuristic::from_action(blueprint, &settings, &profile, &group.summary, &context),
                             InteractionEvent::Execute(entity,  ActionSelector::Target(Some(ActionPath{ nodes })), 0x1)
                         ));
                     }
@@ -193,30 +254,31 @@ pub fn strategical_planning_phase(
                     }
                 }
             }
+            let mut visited: Vec<usize> = visited.into_iter().collect();
+            visited.sort_unstable();
             for index in visited.into_iter() {
                 for handle in blueprint_bundle.unit_blueprints.iter() {
                     let Some(next_blueprint) = blueprints.get(handle) else { continue };
                     if !validate_construction(
-                        next_blueprint, None, &group.agent, grid, groups, index
+                        next_blueprint, None, &group.agent, &alliances, &terrain, grid, groups, index
                     ) { continue; }
 
                     candidates.push((
-                        Heuristic::from_construct(next_blueprint, &settings, &group.summary, &context),
+                        Heuristic::from_construct(next_blueprint, &settings, &group.summary, &context, grid.tiles[index].deposit()),
                         InteractionEvent::Construct(group.agent, parent, index, handle.clone())
                     ));
                 }
             }
-            let mut weight_table: WeightTable<InteractionEvent, u32> = Default::default();
+            let mut weight_table: WeightTable<InteractionEvent, f32> = Default::default();
             for (heuristic, event) in candidates.into_iter() {
-                let weight = heuristic.weight();
-                if weight >= 0 {
-                    weight_table.add(event, weight as u32 + 1);
+                let weight = heuristic.weight(&profile);
+                if weight > 0.0 {
+                    weight_table.add(event, weight);
                 }
             }
 
-            rng.next(time.elapsed().as_millis() as u64);
-            let random_index = rng.next_u32(weight_table.total());
-            let Some(event) = weight_table.take(random_index) else { continue };
+            let random_weight = rng.next_f32() * weight_table.total();
+            let Some(event) = weight_table.take(random_weight) else { continue };
             events.send(event);
         }
     }
@@ -1,21 +1,81 @@
 use bevy::prelude::*;
-use crate::common::noise::{HeightSampler, FractalSampler, FractalType, simplex::SimplexNoise};
+use crate::common::noise::{HeightSampler, FractalSampler, FractalType, simplex::SimplexNoise, perlin::PerlinNoise};
+use crate::common::noise::cellular::{CellularNoise, CellularReturn};
+use crate::common::noise::MurMurHash;
+use crate::common::adjacency::TraversableGraph;
 use crate::common::geometry::{unwrap_equirectangular, Icosahedron, sphere::Sphere};
+use std::f32::consts::PI;
 use crate::common::raycast::HitArea;
 use super::hexsphere::HexSphere;
 use super::grid::MapGrid;
 
-#[derive(Component, serde::Deserialize, Clone, Default)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+pub enum TerrainNoise {
+    #[default] Simplex,
+    Perlin,
+    ///Distance to the closest cellular feature point.
+    Cellular,
+    ///Constant value per cell, for voronoi style biomes.
+    Voronoi,
+    ///Planar simplex noise sampled on the equirectangular unwrap of the sphere, kept for older stages.
+    Equirectangular,
+}
+
+///Offsets sampled coordinates by another noise field.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug)]
+pub struct DomainWarp {
+    pub amplitude: f32,
+    pub frequency: f32,
+}
+
+///Post processing rules applied to generated tiles.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub enum GenerationRule {
+    ///Tiles with the highest noise value at least spacing tiles apart.
+    Deposits { count: usize, spacing: usize, variant: usize },
+    ///Chains of blocked tiles following random great circles.
+    Ridges { count: usize, length: usize, variant: Option<usize> },
+    ///Removes blockers around the tile, applied after other rules.
+    Clearing { tile: usize, radius: usize, variant: Option<usize> },
+    ///Manual override of a single tile, applied last.
+    Tile { tile: usize, variant: Option<usize>, blocker: Option<bool> },
+}
+
+#[derive(Component, serde::Serialize, serde::Deserialize, Clone, Default)]
 pub struct AreaBlueprint {
     pub center: Vec3,
     resolution: usize,
     variants: Vec<f32>,
-    noise_frequency: f32,
-    noise_octaves: usize,
-    seed: u32,
+    pub noise_frequency: f32,
+    pub noise_octaves: usize,
+    #[serde(default)] pub noise: TerrainNoise,
+    #[serde(default)] pub fractal: FractalType,
+    #[serde(default = "AreaBlueprint::default_lacunarity")] lacunarity: f32,
+    #[serde(default = "AreaBlueprint::default_gain")] gain: f32,
+    #[serde(default)] warp: Option<DomainWarp>,
+    #[serde(default)] rules: Vec<GenerationRule>,
+    ///Average matter deposit of each tile, unlimited when zero.
+    #[serde(default)] reserve: i32,
+    pub seed: u32,
 }
 
 impl AreaBlueprint {
+    fn default_lacunarity() -> f32 { 2.0 }
+    fn default_gain() -> f32 { 0.5 }
+    pub fn variant_count(&self) -> usize { self.variants.len() }
+    ///Overrides the tile variant or blocker, merged with previous overrides of the same tile.
+    pub fn paint_tile(&mut self, index: usize, next_variant: Option<usize>, next_blocker: Option<bool>){
+        let position = self.rules.iter().position(|rule|matches!(rule, GenerationRule::Tile { tile, .. } if *tile == index));
+        let Some(GenerationRule::Tile { variant, blocker, .. }) = position.map(|i|&mut self.rules[i]) else {
+            self.rules.push(GenerationRule::Tile { tile: index, variant: next_variant, blocker: next_blocker });
+            return
+        };
+        if next_variant.is_some() { *variant = next_variant; }
+        if next_blocker.is_some() { *blocker = next_blocker; }
+    }
+    pub fn clear_tile(&mut self, index: usize){
+        self.rules.retain(|rule|!matches!(rule, GenerationRule::Tile { tile, .. } if *tile == index));
+    }
     pub fn radius(&self) -> f32 { 1.0 / Icosahedron::circumscribed_tile_radius(self.resolution) }
     pub fn load(&self) -> (Mesh, Transform, HitArea, MapGrid) {
         let mut hexsphere = HexSphere::new(self.resolution, false);
@@ -31,20 +91,110 @@ impl AreaBlueprint {
     }
     fn regenerate(&self, target: &mut HexSphere, seed: u32){
         target.variants = self.variants.len();
-        let mut noise = FractalSampler::from(SimplexNoise::<Vec2>::default());
-        noise.fractal_type = FractalType::Fbm;
-        noise.frequency = (target.graph.len() as f32).sqrt() * self.noise_frequency;
-        noise.octaves = self.noise_octaves;
+        let heights = match self.noise {
+            TerrainNoise::Equirectangular => self.sample_heights(target, SimplexNoise::<Vec2>::default(), seed, 1.0,
+                |position|Vec2::from(unwrap_equirectangular(&position.into()))),
+            TerrainNoise::Simplex => self.sample_heights(target, SimplexNoise::<Vec3>::default(), seed, PI.recip(), Vec3::from),
+            TerrainNoise::Perlin => self.sample_heights(target, PerlinNoise::default(), seed, PI.recip(), Vec3::from),
+            TerrainNoise::Cellular => self.sample_heights(target, CellularNoise::default(), seed, PI.recip(), Vec3::from),
+            TerrainNoise::Voronoi => self.sample_heights(target, CellularNoise {
+                return_type: CellularReturn::Cell, ..Default::default()
+            }, seed, PI.recip(), Vec3::from),
+        };
         for (index, tile) in target.tiles.iter_mut().enumerate() {
-            let position = tile.transform.translation.into();
-            let uv = Vec2::from(unwrap_equirectangular(&position));
-
-            let height = 0.5 + 0.5 * noise.sample(uv, seed);
-            tile.variant = self.variants.iter().position(|&threshold|height <= threshold).unwrap_or_default();
+            tile.variant = self.variants.iter().position(|&threshold|heights[index] <= threshold).unwrap_or_default();
+            tile.capacity = (self.reserve as f32 * (0.5 + heights[index])).round() as i32;
+            tile.reserve = tile.capacity;
 
             if target.graph.neighbors(index).filter(|list|list.len() == 5).is_some() {
                 tile.flags |= MapGrid::BLOCKER;
             }
         }
+        let mut rng = MurMurHash::from_seed(seed as u64);
+        for rule in self.rules.iter() { match rule {
+            GenerationRule::Deposits { count, spacing, variant } => {
+                let mut candidates: Vec<usize> = (0..target.tiles.len()).collect();
+                candidates.sort_by(|&a, &b|heights[b].total_cmp(&heights[a]));
+                let mut excluded: Vec<bool> = vec![false; target.tiles.len()];
+                let mut placed: usize = 0;
+                for index in candidates.into_iter() {
+                    if placed >= *count { break; }
+                    if excluded[index] || target.tiles[index].flags & MapGrid::BLOCKER != 0 { continue; }
+                    target.tiles[index].variant = *variant;
+                    target.tiles[index].capacity *= 2;
+                    target.tiles[index].reserve = target.tiles[index].capacity;
+                    placed += 1;
+                    for adjacent in target.graph.iter_breadth_first().with_origin(index).with_limit(spacing.saturating_sub(1)) {
+                        excluded[adjacent] = true;
+                    }
+                }
+            },
+            GenerationRule::Ridges { count, length, variant } => for _ in 0..*count {
+                let mut index = rng.next_u32(target.tiles.len() as u32) as usize;
+                let Some(&next) = target.graph.neighbors(index)
+                .and_then(|list|list.get(rng.next_u32(list.len() as u32) as usize)) else { continue };
+                let mut direction = target.tiles[next].transform.translation - target.tiles[index].transform.translation;
+                for _ in 0..*length {
+                    target.tiles[index].flags |= MapGrid::BLOCKER;
+                    if let Some(variant) = variant { target.tiles[index].variant = *variant; }
+                    let origin = target.tiles[index].transform.translation;
+                    let Some(&next) = target.graph.neighbors(index).into_iter().flatten()
+                    .filter(|&&adjacent|target.tiles[adjacent].flags & MapGrid::BLOCKER == 0)
+                    .map(|adjacent|(adjacent, (target.tiles[*adjacent].transform.translation - origin).normalize()
+                        .dot(direction.normalize()) + 0.5 * rng.next_f32()))
+                    .max_by(|a, b|a.1.total_cmp(&b.1))
+                    .map(|(adjacent, _)|adjacent) else { break };
+                    direction = target.tiles[next].transform.translation - origin;
+                    index = next;
+                }
+            },
+            GenerationRule::Clearing { .. } | GenerationRule::Tile { .. } => {},
+        } }
+        for rule in self.rules.iter() {
+            let GenerationRule::Clearing { tile, radius, variant } = rule else { continue };
+            for index in target.graph.iter_breadth_first().with_origin(*tile).with_limit(*radius) {
+                if target.graph.neighbors(index).filter(|list|list.len() == 5).is_none() {
+                    target.tiles[index].flags &= !MapGrid::BLOCKER;
+                }
+                if let Some(variant) = variant { target.tiles[index].variant = *variant; }
+            }
+        }
+        for rule in self.rules.iter() {
+            let GenerationRule::Tile { tile, variant, blocker } = rule else { continue };
+            let Some(target_tile) = target.tiles.get_mut(*tile) else { continue };
+            if let Some(variant) = variant { target_tile.variant = *variant; }
+            match blocker {
+                Some(true) => target_tile.flags |= MapGrid::BLOCKER,
+                Some(false) if target.graph.neighbors(*tile).filter(|list|list.len() == 5).is_none() =>
+                    target_tile.flags &= !MapGrid::BLOCKER,
+                _ => {}
+            }
+        }
     }
-}
\ No newline at end of file
+    fn sample_heights<T>(
+        &self, target: &HexSphere, sampler: impl HeightSampler<T>, seed: u32,
+        frequency: f32, project: impl Fn(Vec3) -> T
+    ) -> Vec<f32> where T: std::ops::MulAssign<f32> + Copy {
+        let mut noise = FractalSampler::from(sampler);
+        noise.fractal_type = self.fractal;
+        noise.frequency = (target.graph.len() as f32).sqrt() * self.noise_frequency * frequency;
+        noise.octaves = self.noise_octaves;
+        noise.lacunarity = self.lacunarity;
+        noise.gain = self.gain;
+        let mut warp = FractalSampler::from(SimplexNoise::<Vec3>::default());
+        warp.fractal_type = FractalType::None;
+        warp.frequency = self.warp.map_or(0.0, |warp|warp.frequency);
+        target.tiles.iter().map(|tile|{
+            let mut position = tile.transform.translation;
+            if let Some(DomainWarp { amplitude, .. }) = self.warp {
+                position = position.normalize();
+                position += amplitude * Vec3::new(
+                    warp.sample(position, seed.wrapping_add(101)),
+                    warp.sample(position, seed.wrapping_add(102)),
+                    warp.sample(position, seed.wrapping_add(103)),
+                );
+            }
+            0.5 + 0.5 * noise.sample(project(position), seed)
+        }).collect()
+    }
+}
@@ -2,10 +2,14 @@ use bevy::prelude::*;
 use bevy::utils::HashMap;
 use std::collections::VecDeque;
 use crate::common::adjacency::Graph;
+use super::variant::TerrainVariantTable;
 
 #[derive(Component, Deref, DerefMut, Clone, Copy, PartialEq, Eq)]
 pub struct GridTileIndex(pub usize);
 
+#[derive(Component, Deref, DerefMut, Clone, Copy, PartialEq, Eq)]
+pub struct GridAreaIndex(pub usize);
+
 #[derive(Clone, Default, Debug)]
 pub struct MapGridTile {
     pub transform: Transform,
@@ -13,7 +17,10 @@ pub struct MapGridTile {
 
     pub variant: usize,
     pub flags: u8,
-    pub reference: Option<Entity>
+    pub reference: Option<Entity>,
+    ///Remaining matter deposit, unlimited when the capacity is zero.
+    pub reserve: i32,
+    pub capacity: i32,
 }
 
 impl From<Mat4> for MapGridTile {
@@ -26,6 +33,11 @@ impl From<Mat4> for MapGridTile {
 
 impl MapGridTile {
     pub fn is_empty(&self) -> bool { self.flags == 0 && self.reference.is_none() }
+    ///Extraction rate multiplier, declines once less than half of the deposit remains.
+    pub fn deposit(&self) -> f32 {
+        if self.capacity <= 0 { return 1.0; }
+        (2 * self.reserve).clamp(0, self.capacity) as f32 / self.capacity as f32
+    }
     pub fn set_entity(&mut self, entity: Entity){
         self.flags = MapGrid::BLOCKER | MapGrid::OWNERSHIP;
         self.reference = Some(entity);
@@ -61,6 +73,10 @@ impl MapGrid {
             start_index = if min_index != start_index { min_index }else{ break start_index };
         }
     }
+    pub fn movement_cost(&self, terrain: &TerrainVariantTable, from: usize, to: usize) -> f32 {
+        terrain.movement(self.tiles[to].variant) *
+        self.tiles[from].transform.translation.distance(self.tiles[to].transform.translation)
+    }
     pub fn iter_adjacent_groups<'a>(&'a self, index: usize) -> impl Iterator<Item = &'a usize> {
         self.graph.neighbors(index).unwrap_or_default().iter()
         .filter(|&i|self.tiles[*i].flags & MapGrid::OWNERSHIP != 0)
@@ -1,4 +1,5 @@
 pub mod grid;
 pub mod generation;
 pub mod hexsphere;
-pub mod lookup;
\ No newline at end of file
+pub mod lookup;
+pub mod variant;
\ No newline at end of file
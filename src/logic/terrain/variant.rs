@@ -0,0 +1,30 @@
+use bevy::prelude::*;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct TerrainVariant {
+    ///Multiplier of the time it takes to cross the tile.
+    #[serde(default = "TerrainVariant::default_movement")] pub movement: f32,
+    #[serde(default = "TerrainVariant::default_buildable")] pub buildable: bool,
+    ///Fraction of the damage absorbed by units on the tile.
+    #[serde(default)] pub defence: f32,
+}
+impl TerrainVariant {
+    fn default_movement() -> f32 { 1.0 }
+    fn default_buildable() -> bool { true }
+}
+impl Default for TerrainVariant {
+    fn default() -> Self { Self {
+        movement: Self::default_movement(),
+        buildable: Self::default_buildable(),
+        defence: 0.0,
+    } }
+}
+
+///Properties of each tile variant, missing entries fall back to defaults.
+#[derive(Resource, serde::Serialize, serde::Deserialize, Deref, Clone, Default, Debug)]
+pub struct TerrainVariantTable(pub Vec<TerrainVariant>);
+impl TerrainVariantTable {
+    pub fn movement(&self, variant: usize) -> f32 { self.get(variant).map_or(1.0, |entry|entry.movement) }
+    pub fn buildable(&self, variant: usize) -> bool { self.get(variant).map_or(true, |entry|entry.buildable) }
+    pub fn defence(&self, variant: usize) -> f32 { self.get(variant).map_or(0.0, |entry|entry.defence) }
+}
@@ -0,0 +1,124 @@
+use bevy::prelude::*;
+use bevy::ecs::system::SystemParam;
+use crate::common::adjacency::TraversableGraph;
+use super::{Agent, AllianceTable, MapGrid, GridTileIndex, GroupLink};
+
+#[derive(Component, serde::Deserialize, Deref, Clone, Copy, Debug)]
+pub struct SightRadius(pub usize);
+impl Default for SightRadius {
+    fn default() -> Self { Self(2) }
+}
+
+///Per tile bitmasks of agents currently seeing and having ever seen the tile.
+///Revealed tiles stay visible regardless of unit sight, tiles without data are hidden.
+///Sighted tiles hold a hostile structure as last seen by the agent, they are kept until seen again.
+#[derive(Component, Clone, Default)]
+pub struct GridVisibility {
+    pub visible: Vec<u32>,
+    pub explored: Vec<u32>,
+    pub revealed: Vec<u32>,
+    pub sighted: Vec<u32>,
+}
+impl GridVisibility {
+    pub fn is_visible(&self, index: usize, mask: u32) -> bool { self.visible.get(index).map_or(false, |bits|bits & mask != 0) }
+    pub fn is_explored(&self, index: usize, mask: u32) -> bool { self.explored.get(index).map_or(false, |bits|bits & mask != 0) }
+    pub fn is_sighted(&self, index: usize, agent: &Agent) -> bool { self.sighted.get(index).map_or(false, |bits|bits & agent.mask() != 0) }
+}
+
+pub fn update_visibility_phase(
+    alliances: Res<AllianceTable>,
+    mut query_grid: Query<(&MapGrid, &mut GridVisibility)>,
+    query_unit: Query<(&Parent, &Agent, &GridTileIndex, &SightRadius)>,
+    query_structure: Query<&Agent, With<GroupLink>>,
+){
+    for (grid, mut visibility) in query_grid.iter_mut() {
+        let visibility = visibility.as_mut();
+        visibility.visible.clear();
+        visibility.visible.resize(grid.tiles.len(), 0);
+        visibility.explored.resize(grid.tiles.len(), 0);
+        visibility.revealed.resize(grid.tiles.len(), 0);
+        visibility.sighted.resize(grid.tiles.len(), 0);
+        for index in 0..grid.tiles.len() {
+            visibility.visible[index] |= visibility.revealed[index];
+            visibility.explored[index] |= visibility.revealed[index];
+        }
+    }
+    for (parent, agent, tile_index, sight) in query_unit.iter() {
+        let Ok((grid, mut visibility)) = query_grid.get_mut(parent.get()) else { continue };
+        let mask = agent.mask();
+        for index in grid.graph.iter_breadth_first().with_origin(**tile_index).with_limit(**sight) {
+            visibility.visible[index] |= mask;
+            visibility.explored[index] |= mask;
+        }
+    }
+    let mut agents: Vec<Agent> = query_unit.iter().map(|(_, agent, _, _)|*agent).collect();
+    agents.sort_unstable_by_key(Agent::mask);
+    agents.dedup();
+    for (grid, mut visibility) in query_grid.iter_mut() {
+        let visibility = visibility.as_mut();
+        for agent in agents.iter() {
+            let mask = alliances.vision_mask(agent);
+            for (index, tile) in grid.tiles.iter().enumerate() {
+                if visibility.visible[index] & mask == 0 { continue; }
+                let hostile = tile.reference.and_then(|entity|query_structure.get(entity).ok())
+                    .map_or(false, |target|alliances.is_hostile(agent, target));
+                if hostile {
+                    visibility.sighted[index] |= agent.mask();
+                } else {
+                    visibility.sighted[index] &= !agent.mask();
+                }
+            }
+        }
+    }
+}
+
+#[derive(SystemParam)]
+pub struct VisibilityLookup<'w, 's> {
+    alliances: Res<'w, AllianceTable>,
+    query_grid: Query<'w, 's, &'static GridVisibility>,
+    query_unit: Query<'w, 's, (&'static Parent, &'static GridTileIndex)>,
+}
+impl<'w, 's> VisibilityLookup<'w, 's> {
+    ///Whether the agent or any of its allies currently sees the entity.
+    pub fn is_visible(&self, agent: &Agent, entity: Entity) -> bool {
+        let Ok((parent, tile_index)) = self.query_unit.get(entity) else { return false };
+        let Ok(visibility) = self.query_grid.get(parent.get()) else { return false };
+        visibility.is_visible(**tile_index, self.alliances.vision_mask(agent))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::MapGridTile;
+    use crate::common::adjacency::Graph;
+    use crate::common::geometry::MeshGeometry;
+
+    #[test]
+    pub fn hostile_structures_remembered_until_seen(){
+        let mut world = World::new();
+        world.init_resource::<AllianceTable>();
+        let geometry = MeshGeometry { vertices: vec![bevy::math::Vec3A::ZERO; 3], indices: vec![0, 1, 2] };
+        let mut grid = MapGrid { tiles: vec![MapGridTile::default(); 3], graph: Graph::from(&geometry), ..Default::default() };
+        let structure = world.spawn((GridTileIndex(1), Agent::Player, GroupLink::default(), SightRadius(0))).id();
+        let scout = world.spawn((GridTileIndex(0), Agent::AI(0), SightRadius(1))).id();
+        grid.tiles[1].set_entity(structure);
+        let parent = world.spawn((grid, GridVisibility::default())).push_children(&[structure, scout]).id();
+
+        let mut schedule = Schedule::new();
+        schedule.add_system(update_visibility_phase);
+        schedule.run(&mut world);
+        let visibility = world.get::<GridVisibility>(parent).unwrap();
+        assert!(visibility.is_sighted(1, &Agent::AI(0)));
+        assert!(!visibility.is_sighted(1, &Agent::Player));
+
+        world.get_mut::<SightRadius>(scout).unwrap().0 = 0;
+        world.get_mut::<MapGrid>(parent).unwrap().tiles[1].reference = None;
+        schedule.run(&mut world);
+        assert!(world.get::<GridVisibility>(parent).unwrap().is_sighted(1, &Agent::AI(0)));
+
+        world.get_mut::<SightRadius>(scout).unwrap().0 = 1;
+        schedule.run(&mut world);
+        assert!(!world.get::<GridVisibility>(parent).unwrap().is_sighted(1, &Agent::AI(0)));
+    }
+}
@@ -16,7 +16,17 @@ use bevy_hanabi::prelude::*;
 use bevy_kira_audio::prelude::*;
 
 fn main() {
-    App::new()
+    if std::env::args().any(|arg|arg == "--headless") {
+        let mut app = App::new();
+        app
+        .add_plugins(MinimalPlugins)
+        .add_plugin(bevy::log::LogPlugin::default())
+        .add_plugin(scene::HeadlessSimulationPlugin);
+        configure_replay(&mut app).run();
+        return;
+    }
+    let mut app = App::new();
+    app
     .insert_resource(Msaa::Off)
     .insert_resource(ClearColor(Color::DARK_GRAY))
     .add_plugins(DefaultPlugins.set(WindowPlugin {
@@ -42,6 +52,23 @@ fn main() {
     .add_plugin(interaction::InteractionPlugin)
     .add_plugin(interface::InterfacePlugin)
     .add_plugin(effects::EffectsPlugin)
-    .add_plugin(scene::DemoPlugin)
-    .run();
+    .add_plugin(scene::DemoPlugin);
+    configure_replay(&mut app).run();
+}
+
+fn configure_replay(app: &mut App) -> &mut App {
+    let args: Vec<String> = std::env::args().collect();
+    for pair in args.windows(2) {
+        match pair[0].as_str() {
+            "--record" => {
+                app.insert_resource(interaction::ReplayRecorder{ path: Some(pair[1].clone().into()), ..Default::default() });
+            },
+            "--replay" => match interaction::Replay::load(&pair[1]) {
+                Ok(replay) => { app.insert_resource(interaction::ReplayPlayback::from(replay)); },
+                Err(error) => error!("failed to load replay {}: {}", pair[1], error),
+            },
+            _ => {}
+        }
+    }
+    app
 }
\ No newline at end of file
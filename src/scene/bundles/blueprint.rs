@@ -4,14 +4,15 @@ use bevy::reflect::TypeUuid;
 use bevy::ecs::system::EntityCommands;
 use crate::common::loader::{AssetBundleList, ScopedAssetServer};
 use crate::extensions::CommandsExtension;
+use crate::scene::StartPlacement;
 use crate::effects::animation::{UnitAnimation, MovementVariant, MovementFormation};
 use crate::logic::{
     Agent, AreaBlueprint, GlobalEconomy,
-    BoundingRadius, UnitDirective, UnderConstruction, Integrity, Velocity,
-    MatterBinding, UpgradeDistribution, UnitFabrication, MilitaryBinding, StrategySettings,
+    BoundingRadius, UnitDirective, UnderConstruction, Integrity, Velocity, SightRadius,
+    MatterBinding, UpgradeDistribution, UnitFabrication, MilitaryBinding, StrategySettings, StageObjectives, ObjectiveCondition, StageTriggers, TriggerCondition, AllianceTable, TerrainVariantTable,
 };
 
-#[derive(serde::Deserialize, Clone, Default)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
 pub struct UnitPlacement {
     pub key: String,
     pub area: usize,
@@ -19,13 +20,75 @@ pub struct UnitPlacement {
     pub agent: Agent,
 }
 
-#[derive(serde::Deserialize, TypeUuid, Clone, Default)]
+#[derive(serde::Serialize, serde::Deserialize, TypeUuid, Clone, Default)]
 #[uuid = "e5dcb5ed-95f4-4061-aea2-09dc6253135f"]
 pub struct StageBlueprint {
+    ///Defaults to the file name of the stage.
+    #[serde(default)] pub name: String,
+    #[serde(default)] pub description: String,
+    ///Stages without order are listed last.
+    #[serde(default = "StageBlueprint::default_order")] pub order: i32,
     pub economy: GlobalEconomy,
     pub strategy: StrategySettings,
+    #[serde(default)] pub objectives: StageObjectives,
+    #[serde(default)] pub triggers: StageTriggers,
+    #[serde(default)] pub alliances: AllianceTable,
+    #[serde(default)] pub terrain: TerrainVariantTable,
     pub areas: Vec<AreaBlueprint>,
     pub units: Vec<UnitPlacement>,
+    #[serde(default)] pub starts: Option<StartPlacement>,
+}
+impl StageBlueprint {
+    pub fn default_order() -> i32 { i32::MAX }
+    ///Placement index of the first unit spawned by triggers, after the fixed units and start kits.
+    pub fn spawn_offset(&self) -> usize {
+        self.units.len() + self.starts.as_ref().map_or(0, StartPlacement::slots)
+    }
+    pub fn validate(&self) -> Result<(), String> {
+        let mut agents = self.units.iter().map(|placement|&placement.agent)
+            .chain(self.starts.iter().flat_map(|starts|starts.agents.iter()))
+            .chain(self.alliances.teams.iter().flatten())
+            .chain(self.alliances.neutral.iter())
+            .chain(self.objectives.iter().map(|objective|&objective.agent));
+        if let Some(agent) = agents.find(|agent|!agent.is_valid()) {
+            return Err(format!("invalid agent {:?}", agent));
+        }
+        let placements = self.spawn_offset() + self.triggers.spawn_count();
+        if let Some(unit) = self.objectives.iter().find_map(|objective|match objective.condition {
+            ObjectiveCondition::Destroy { unit } if unit >= placements => Some(unit),
+            _ => None
+        }) {
+            return Err(format!("invalid placement {}", unit));
+        }
+        self.triggers.validate(placements)
+    }
+    ///Places a fixed unit after the existing ones, shifting references to start kits and spawned units.
+    pub fn insert_placement(&mut self, placement: UnitPlacement) -> usize {
+        let index = self.units.len();
+        self.remap_placements(|unit|Some(if unit >= index { unit + 1 }else{ unit }));
+        self.units.push(placement);
+        index
+    }
+    ///Removes a fixed unit, objectives referencing it are dropped and trigger conditions never fire.
+    pub fn remove_placement(&mut self, index: usize) -> UnitPlacement {
+        self.remap_placements(|unit|match unit.cmp(&index) {
+            std::cmp::Ordering::Less => Some(unit),
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Greater => Some(unit - 1),
+        });
+        self.units.remove(index)
+    }
+    fn remap_placements(&mut self, remap: impl Fn(usize) -> Option<usize>) {
+        self.objectives.0.retain_mut(|objective|match &mut objective.condition {
+            ObjectiveCondition::Destroy { unit } => remap(*unit).map(|next|*unit = next).is_some(),
+            _ => true
+        });
+        for trigger in self.triggers.0.iter_mut() {
+            if !trigger.condition.remap_placements(&remap) {
+                trigger.condition = TriggerCondition::Any(Vec::new());
+            }
+        }
+    }
 }
 
 #[derive(serde::Deserialize, TypeUuid, Clone, Default, Debug)]
@@ -41,6 +104,7 @@ pub struct UnitBlueprint {
     pub animation: Option<UnitAnimation>,
     pub movement: Option<MovementVariant>,
     pub velocity: Velocity,
+    #[serde(default)] pub sight: SightRadius,
     pub action: Option<UnitDirective>,
 
     pub construction: UnderConstruction,
@@ -55,6 +119,7 @@ impl UnitBlueprint {
         commands.insert(self.radius.clone());
         commands.insert_add(self.integrity.clone());
         if self.velocity.0 != 0 { commands.insert(self.velocity.clone()); }
+        commands.insert(self.sight);
         if structure { commands.insert(self.construction.clone()); }
         if let Some(movement) = self.movement.as_ref() {
             commands.insert(MovementFormation{ variant: movement.clone(), ..Default::default() });
@@ -66,8 +131,11 @@ impl UnitBlueprint {
     }
 }
 
+#[derive(Resource, Deref, DerefMut, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct StageSelection(pub usize);
+
 pub struct BlueprintAssetBundle {
-    pub intro_stage: Handle<StageBlueprint>,
+    pub stages: Vec<Handle<StageBlueprint>>,
     pub unit_blueprints: Vec<Handle<UnitBlueprint>>,
     mapping: HashMap<String, usize>,
 }
@@ -78,11 +146,28 @@ impl BlueprintAssetBundle {
 }
 impl AssetBundleList for BlueprintAssetBundle {
     fn from_asset_server(asset_server: &ScopedAssetServer) -> Self { Self {
-        intro_stage: asset_server.load("data/intro.stage.ron"),
+        stages: asset_server.load_folder("data/stages"),
         unit_blueprints: asset_server.load_folder("data/units"),
         mapping: HashMap::new(),
     } }
     fn prepare(&mut self, world: &mut World) {
+        let asset_server = world.resource::<AssetServer>().clone();
+        let mut stages = world.resource_mut::<Assets<StageBlueprint>>();
+        for handle in self.stages.iter() {
+            let Some(stage) = stages.get_mut(handle).filter(|stage|stage.name.is_empty()) else { continue };
+            let Some(path) = asset_server.get_handle_path(handle) else { continue };
+            let Some(stem) = path.path().file_name().and_then(|name|name.to_str()) else { continue };
+            stage.name = stem.split('.').next().unwrap_or_default().to_string();
+        }
+        let stages = world.resource::<Assets<StageBlueprint>>();
+        self.stages.retain(|handle|match stages.get(handle).unwrap().validate() {
+            Ok(()) => true,
+            Err(error) => { warn!("skipping invalid stage {:?}: {}", asset_server.get_handle_path(handle), error); false },
+        });
+        self.stages.sort_by_cached_key(|handle|{
+            let stage = stages.get(handle).unwrap();
+            (stage.order, stage.name.clone())
+        });
         let blueprints = world.resource::<Assets<UnitBlueprint>>();
         for (index, handle) in self.unit_blueprints.iter().enumerate() {
             let blueprint = blueprints.get(handle).unwrap();
@@ -52,7 +52,7 @@ pub fn update_camera_view(
     }
     
     let next_distance = lerp(distance.range.0, distance.range.1, camera.zoom_ease.calculate(camera.zoom));
-    distance.distance = lerp(distance.distance, next_distance, camera.zoom_smoothing.calculate(time.delta_seconds()));
+    distance.distance = lerp(distance.distance, next_distance, camera.zoom_smoothing.calculate(time.raw_delta_seconds()));
 
     if camera.prev_rotation.is_some() { return; }
     let Ok((grid, selection, transform)) = query_grid.get_single() else { return };
@@ -64,5 +64,5 @@ pub fn update_camera_view(
     let next_normal = (tile_center_position - grid_center_position).normalize();
     let prev_normal = rotation.mul_vec3(Vec3::Z);
     let rotate = Quat::from_rotation_arc(prev_normal, next_normal);
-    *rotation = Quat::slerp(prev_rotation, rotate * prev_rotation, camera.center_smoothing.calculate(time.delta_seconds()));
+    *rotation = Quat::slerp(prev_rotation, rotate * prev_rotation, camera.center_smoothing.calculate(time.raw_delta_seconds()));
 }
\ No newline at end of file
@@ -0,0 +1,275 @@
+use bevy::prelude::*;
+use std::path::Path;
+use crate::common::loader::AssetBundle;
+use crate::common::noise::FractalType;
+use crate::logic::{Agent, MapGrid, GridAreaIndex, NetworkGroupList, ConstructionEvent, TerrainNoise};
+use crate::interaction::GridSelection;
+use super::{GlobalState, StageBlueprint, UnitBlueprint, UnitPlacement, BlueprintAssetBundle, EnvironmentAssetBundle, ModelAssetBundle};
+use super::stage::{spawn_stage_area, place_stage_units, resolve_stage_starts};
+
+///Kept outside of the stage folder, the export is added to the catalogue by moving it there.
+pub const EDITOR_EXPORT_PATH: &str = "assets/data/editor.stage.ron";
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum EditorTool {
+    #[default] Variant,
+    Blocker,
+    Unit,
+    Erase,
+}
+impl EditorTool {
+    pub fn next(&self) -> Self { match self {
+        EditorTool::Variant => EditorTool::Blocker,
+        EditorTool::Blocker => EditorTool::Unit,
+        EditorTool::Unit => EditorTool::Erase,
+        EditorTool::Erase => EditorTool::Variant,
+    } }
+}
+
+///Working copy of the stage modified in the editor.
+#[derive(Resource, Clone, Default)]
+pub struct StageEditor {
+    pub stage: StageBlueprint,
+    pub area: usize,
+    pub tool: EditorTool,
+    pub agent: Agent,
+    pub variant: usize,
+    pub unit: usize,
+    ///Area to regenerate after an edit.
+    pub changed: Option<usize>,
+    ///Start placements kept for the duration of a brush stroke.
+    pub starts: Option<Vec<(usize, UnitPlacement)>>,
+}
+impl From<StageBlueprint> for StageEditor {
+    fn from(stage: StageBlueprint) -> Self { Self { stage, ..Default::default() } }
+}
+impl StageEditor {
+    ///Serialized copy of the stage, renamed and listed last so that it does not replace its source.
+    pub fn export(&self) -> Result<String, serde_ron::Error> {
+        let mut stage = self.stage.clone();
+        if !stage.name.ends_with(" (edited)") { stage.name.push_str(" (edited)"); }
+        stage.order = StageBlueprint::default_order();
+        let config = serde_ron::ser::PrettyConfig::default().struct_names(true);
+        serde_ron::ser::to_string_pretty(&stage, config)
+    }
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, self.export()?)?;
+        Ok(())
+    }
+    ///Cycles through the player and every AI agent with a visibility mask bit.
+    fn next_agent(agent: Agent) -> Agent { match agent {
+        Agent::Player => Agent::AI(0),
+        Agent::AI(index) if index < Agent::MAX_AI_INDEX => Agent::AI(index + 1),
+        Agent::AI(_) => Agent::Player,
+    } }
+}
+
+pub fn update_stage_editor(
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    blueprints: Res<Assets<UnitBlueprint>>,
+    blueprint_bundle: Res<AssetBundle<BlueprintAssetBundle>>,
+    mut editor: ResMut<StageEditor>,
+    mut next_state: ResMut<NextState<GlobalState>>,
+    mut stroke: Local<Option<(usize, usize, bool)>>,
+    query_grid: Query<(&GridAreaIndex, &GridSelection, &MapGrid)>,
+){
+    let editor = editor.as_mut();
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(GlobalState::Menu);
+        return;
+    }
+    if keys.just_pressed(KeyCode::F5) {
+        match editor.save(EDITOR_EXPORT_PATH) {
+            Ok(_) => info!("exported stage {}", EDITOR_EXPORT_PATH),
+            Err(error) => error!("failed to export stage {}: {}", EDITOR_EXPORT_PATH, error),
+        }
+    }
+    if keys.just_pressed(KeyCode::Tab) { editor.tool = editor.tool.next(); }
+    if keys.just_pressed(KeyCode::A) { editor.agent = StageEditor::next_agent(editor.agent); }
+    let step: isize = keys.just_pressed(KeyCode::E) as isize - keys.just_pressed(KeyCode::Q) as isize;
+    if step != 0 { match editor.tool {
+        EditorTool::Variant => {
+            let count = editor.stage.areas.get(editor.area).map_or(1, |area|area.variant_count().max(1));
+            editor.variant = (editor.variant as isize + step).rem_euclid(count as isize) as usize;
+        },
+        EditorTool::Unit => {
+            let count = blueprint_bundle.unit_blueprints.len().max(1);
+            editor.unit = (editor.unit as isize + step).rem_euclid(count as isize) as usize;
+        },
+        _ => {}
+    } }
+
+    let area_index = editor.area;
+    if let Some(area) = editor.stage.areas.get_mut(area_index) {
+        let mut regenerate = true;
+        if keys.just_pressed(KeyCode::N) {
+            area.noise = match area.noise {
+                TerrainNoise::Equirectangular => TerrainNoise::Simplex,
+                TerrainNoise::Simplex => TerrainNoise::Perlin,
+                TerrainNoise::Perlin => TerrainNoise::Cellular,
+                TerrainNoise::Cellular => TerrainNoise::Voronoi,
+                TerrainNoise::Voronoi => TerrainNoise::Equirectangular,
+            };
+        } else if keys.just_pressed(KeyCode::F) {
+            area.fractal = match area.fractal {
+                FractalType::None => FractalType::Fbm,
+                FractalType::Fbm => FractalType::Ridged,
+                FractalType::Ridged => FractalType::PingPong(2.0),
+                FractalType::PingPong(_) => FractalType::None,
+            };
+        } else if keys.just_pressed(KeyCode::Up) {
+            area.noise_frequency += 0.005;
+        } else if keys.just_pressed(KeyCode::Down) {
+            area.noise_frequency = (area.noise_frequency - 0.005).max(0.005);
+        } else if keys.just_pressed(KeyCode::Right) {
+            area.noise_octaves += 1;
+        } else if keys.just_pressed(KeyCode::Left) {
+            area.noise_octaves = area.noise_octaves.saturating_sub(1).max(1);
+        } else if keys.just_pressed(KeyCode::G) {
+            area.seed = area.seed.wrapping_add(1);
+        } else {
+            regenerate = false;
+        }
+        if regenerate {
+            editor.changed = Some(area_index);
+            editor.starts = None;
+        }
+    }
+
+    if !mouse_buttons.pressed(MouseButton::Right) {
+        if let Some((area_index, _, _)) = stroke.take() {
+            editor.changed = Some(area_index);
+            editor.starts = None;
+        }
+        return;
+    }
+    let Some((area_index, selection, blocked)) = query_grid.iter().next()
+        .map(|(area_index, selection, grid)|(**area_index, **selection, grid.tiles[**selection].flags & MapGrid::BLOCKER != 0)) else { return };
+    editor.area = area_index;
+    if stroke.map_or(false, |(area, tile, _)|area == area_index && tile == selection) { return; }
+    let Some(area) = editor.stage.areas.get_mut(area_index) else { return };
+    match editor.tool {
+        EditorTool::Variant => area.paint_tile(selection, Some(editor.variant), None),
+        EditorTool::Blocker => {
+            let blocker = stroke.map_or(!blocked, |(_, _, blocker)|blocker);
+            area.paint_tile(selection, None, Some(blocker));
+            stroke.replace((area_index, selection, blocker));
+        },
+        EditorTool::Unit => {
+            let Some(blueprint) = blueprint_bundle.unit_blueprints.get(editor.unit)
+                .and_then(|handle|blueprints.get(handle)) else { return };
+            let placement = UnitPlacement {
+                key: blueprint.key.clone(), area: area_index, tile: selection, agent: editor.agent,
+            };
+            match editor.stage.units.iter_mut().find(|placement|placement.area == area_index && placement.tile == selection) {
+                Some(previous) => *previous = placement,
+                None => { editor.stage.insert_placement(placement); },
+            }
+        },
+        EditorTool::Erase => {
+            area.clear_tile(selection);
+            if let Some(index) = editor.stage.units.iter().position(|placement|placement.area == area_index && placement.tile == selection) {
+                editor.stage.remove_placement(index);
+            }
+        },
+    }
+    if editor.tool != EditorTool::Blocker { stroke.replace((area_index, selection, false)); }
+    editor.changed = Some(area_index);
+}
+
+pub fn rebuild_stage_editor(
+    mut commands: Commands,
+    mut construction_events: EventWriter<ConstructionEvent>,
+    mut editor: ResMut<StageEditor>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    blueprints: Res<Assets<UnitBlueprint>>,
+    blueprint_bundle: Res<AssetBundle<BlueprintAssetBundle>>,
+    environment_bundle: Res<AssetBundle<EnvironmentAssetBundle>>,
+    model_bundle: Res<AssetBundle<ModelAssetBundle>>,
+    query_grid: Query<(Entity, &GridAreaIndex), With<NetworkGroupList>>,
+){
+    let editor = editor.as_mut();
+    let Some(area_index) = editor.changed.take() else { return };
+    let Some(area) = editor.stage.areas.get(area_index) else { return };
+    for (entity, _) in query_grid.iter().filter(|(_, index)|***index == area_index) {
+        commands.entity(entity).despawn_recursive();
+    }
+    let (entity, mut grid) = spawn_stage_area(&mut commands, &mut meshes, &environment_bundle, area, area_index);
+    let fixed = editor.stage.units.iter().enumerate().filter(|(_, placement)|placement.area == area_index);
+    place_stage_units(
+        &mut commands, &mut construction_events, entity, &mut grid, Some(&*model_bundle), &blueprints,
+        &blueprint_bundle, fixed
+    );
+    let starts = editor.starts.get_or_insert_with(||resolve_stage_starts(&grid, &editor.stage, area_index));
+    place_stage_units(
+        &mut commands, &mut construction_events, entity, &mut grid, Some(&*model_bundle), &blueprints,
+        &blueprint_bundle, starts.iter().filter(|(_, placement)|placement.area == area_index)
+        .map(|(index, placement)|(*index, placement))
+    );
+    commands.entity(entity).insert(grid);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::{Objective, ObjectiveCondition, ObjectiveOutcome, StageTrigger, TriggerAction, TriggerCondition};
+
+    #[test]
+    pub fn exported_stage_round_trip(){
+        let text = std::fs::read("assets/data/stages/frontier.stage.ron").unwrap();
+        let stage: StageBlueprint = serde_ron::de::from_bytes(&text).unwrap();
+        let exported = StageEditor::from(stage.clone()).export().unwrap();
+        let imported: StageBlueprint = serde_ron::de::from_bytes(exported.as_bytes()).unwrap();
+        assert_eq!(imported.name, format!("{} (edited)", stage.name));
+        assert_eq!(imported.order, StageBlueprint::default_order());
+        assert_eq!(imported.areas.len(), stage.areas.len());
+        assert_eq!(imported.units.len(), stage.units.len());
+        assert_eq!(imported.starts.map(|starts|starts.slots()), stage.starts.map(|starts|starts.slots()));
+    }
+
+    #[test]
+    pub fn placement_references_follow_edits(){
+        use TriggerCondition::*;
+        let placement = |tile: usize|UnitPlacement { key: "vessel".to_string(), tile, ..Default::default() };
+        let destroy = |unit: usize|Objective { agent: Agent::Player, condition: ObjectiveCondition::Destroy { unit }, outcome: ObjectiveOutcome::Victory };
+        let trigger = |condition: TriggerCondition, actions: Vec<TriggerAction>|StageTrigger { condition, actions };
+        let mut stage = StageBlueprint {
+            units: (0..3).map(placement).collect(),
+            ..Default::default()
+        };
+        stage.objectives.0 = vec![destroy(1), destroy(3)];
+        stage.triggers.0 = vec![
+            trigger(Destroyed { unit: 2 }, Vec::new()),
+            trigger(Any(vec![Destroyed { unit: 1 }, Destroyed { unit: 0 }]), Vec::new()),
+            trigger(All(vec![Destroyed { unit: 1 }, Elapsed { seconds: 1.0 }]), Vec::new()),
+            trigger(Elapsed { seconds: 1.0 }, vec![TriggerAction::Spawn(vec![placement(3)])]),
+        ];
+        assert!(stage.validate().is_ok());
+
+        stage.remove_placement(1);
+        assert!(stage.validate().is_ok());
+        assert!(matches!(stage.objectives.as_slice(), [Objective { condition: ObjectiveCondition::Destroy { unit: 2 }, .. }]));
+        assert!(matches!(stage.triggers[0].condition, Destroyed { unit: 1 }));
+        assert!(matches!(&stage.triggers[1].condition, Any(conditions) if matches!(conditions.as_slice(), [Destroyed { unit: 0 }])));
+        assert!(matches!(&stage.triggers[2].condition, Any(conditions) if conditions.is_empty()));
+
+        assert_eq!(stage.insert_placement(placement(4)), 2);
+        assert!(stage.validate().is_ok());
+        assert!(matches!(stage.objectives.as_slice(), [Objective { condition: ObjectiveCondition::Destroy { unit: 3 }, .. }]));
+        assert!(matches!(stage.triggers[0].condition, Destroyed { unit: 1 }));
+    }
+
+    #[test]
+    pub fn agent_cycle_covers_all_agents(){
+        let mut agent = Agent::Player;
+        let mut visited = Vec::new();
+        loop {
+            visited.push(agent);
+            agent = StageEditor::next_agent(agent);
+            if agent == Agent::Player { break; }
+        }
+        assert_eq!(visited.len(), Agent::MAX_AI_INDEX as usize + 2);
+        assert!(visited.iter().all(Agent::is_valid));
+    }
+}
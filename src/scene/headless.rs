@@ -0,0 +1,174 @@
+use bevy::prelude::*;
+use bevy::time::{TimeUpdateStrategy, TimeSystem};
+use bevy::utils::Instant;
+use bevy::app::AppExit;
+use crate::common::loader::{LoaderPlugin, LoadingState, AssetBundle, RonAssetPlugin};
+use crate::logic::LogicPlugin;
+use crate::interaction::{InteractionEvent, ViewMode, ReplayPlugin, process_interaction_event};
+use super::{GlobalState, StageBlueprint, StageSelection, UnitBlueprint, BlueprintAssetBundle};
+
+///Drives the fixed update pipeline on top of MinimalPlugins, without window, renderer, models or effects.
+///Each app update advances the simulation by exactly one fixed tick.
+pub struct HeadlessSimulationPlugin; impl Plugin for HeadlessSimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(AssetPlugin::default());
+        app.add_plugin(TransformPlugin);
+        app.add_plugin(HierarchyPlugin);
+        app.add_plugin(LoaderPlugin);
+
+        app.add_plugin(RonAssetPlugin::<StageBlueprint>::new("stage.ron"));
+        app.add_plugin(RonAssetPlugin::<UnitBlueprint>::new("unit.ron"));
+        app.add_plugin(LogicPlugin);
+
+        app.init_resource::<ViewMode>();
+        app.add_event::<InteractionEvent>();
+        app.add_plugin(ReplayPlugin);
+        app.init_resource::<StageSelection>();
+        app.init_resource::<AssetBundle<BlueprintAssetBundle>>();
+
+        app.add_system(process_interaction_event.in_base_set(CoreSet::PreUpdate));
+        app.add_system(super::stage::load_stage_headless
+            .after(crate::logic::reset_simulation_tick)
+            .in_schedule(OnExit(GlobalState::Menu)));
+        app.add_system(advance_headless_simulation.in_set(OnUpdate(LoadingState::Running)));
+
+        app.insert_resource(TimeUpdateStrategy::ManualInstant(Instant::now()));
+        app.add_system(advance_headless_clock.in_base_set(CoreSet::First).before(TimeSystem));
+    }
+}
+
+fn advance_headless_clock(mut strategy: ResMut<TimeUpdateStrategy>, fixed_time: Res<FixedTime>){
+    if let TimeUpdateStrategy::ManualInstant(instant) = strategy.as_mut() {
+        *instant += fixed_time.period;
+    }
+}
+
+fn advance_headless_simulation(
+    state: Res<State<GlobalState>>,
+    mut next_state: ResMut<NextState<GlobalState>>,
+    mut exit: EventWriter<AppExit>,
+    mut started: Local<bool>,
+){
+    if state.0 != GlobalState::Menu { return; }
+    if *started {
+        exit.send(AppExit);
+    } else {
+        *started = true;
+        next_state.set(GlobalState::Running);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::asset::{LoadState, HandleId};
+    use crate::logic::{Agent, MapGrid, NetworkGroupList, GridTileIndex, Integrity, SimulationTick};
+    use bevy::ecs::system::System;
+    use crate::logic::StrategySettings;
+    use crate::interaction::{Replay, ReplayEntry, ReplayAction, ReplayRecorder, ReplayPlayback};
+    use crate::scene::{MatchSnapshot, PendingSnapshot, capture_match_snapshot};
+
+    ///Loads the assets and enters the first stage, every following update is one fixed tick.
+    fn start_headless_app(setup: impl FnOnce(&mut App)) -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins).add_plugin(HeadlessSimulationPlugin);
+        setup(&mut app);
+
+        let bundle = app.world.resource::<AssetBundle<BlueprintAssetBundle>>();
+        let handles: Vec<HandleId> = bundle.stages.iter().map(|handle|handle.id())
+            .chain(bundle.unit_blueprints.iter().map(|handle|handle.id())).collect();
+        let asset_server = app.world.resource::<AssetServer>().clone();
+        while app.world.resource::<State<LoadingState>>().0 == LoadingState::Loading {
+            assert_ne!(asset_server.get_group_load_state(handles.iter().copied()), LoadState::Failed);
+            app.update();
+        }
+        for _ in 0..2 { app.update(); }
+        assert_eq!(app.world.resource::<State<GlobalState>>().0, GlobalState::Running);
+        app
+    }
+
+    type GroupSummary = (Agent, Vec<usize>, Vec<(i32, i32, i32, i32)>);
+    fn capture_match_state(app: &mut App) -> (Vec<GroupSummary>, Vec<(usize, i32, i32)>) {
+        let mut query_grid = app.world.query::<&NetworkGroupList>();
+        let groups: Vec<GroupSummary> = query_grid.iter(&app.world).flat_map(|groups|groups.iter()).map(|group|(
+            group.agent,
+            group.list.iter().map(|(tile, _)|*tile).collect(),
+            group.summary.0.iter().map(|summary|(summary.amount, summary.reservation, summary.consumption, summary.production)).collect(),
+        )).collect();
+        let mut query_unit = app.world.query::<(&GridTileIndex, &Integrity)>();
+        let mut units: Vec<(usize, i32, i32)> = query_unit.iter(&app.world)
+            .map(|(tile_index, integrity)|(**tile_index, integrity.absorbed, integrity.restored)).collect();
+        units.sort();
+        (groups, units)
+    }
+
+    #[test] pub fn simulate_intro_stage(){
+        let mut app = start_headless_app(|_|{});
+
+        let start = app.world.resource::<SimulationTick>().elapsed;
+        for _ in 0..120 { app.update(); }
+        assert_eq!(app.world.resource::<SimulationTick>().elapsed - start, 120);
+
+        let mut query_grid = app.world.query::<(&MapGrid, &NetworkGroupList)>();
+        let (grid, groups) = query_grid.single(&app.world);
+        assert!(!grid.tiles.is_empty());
+        assert!(groups.iter().any(|group|group.agent == Agent::Player));
+        assert!(groups.iter().any(|group|group.agent != Agent::Player));
+    }
+
+    #[test] pub fn replay_reproduces_match(){
+        const TICKS: usize = 600;
+        let mut app = start_headless_app(|_|{});
+        let mut query_grid = app.world.query::<(Entity, &MapGrid, &NetworkGroupList)>();
+        let (parent, grid, groups) = query_grid.single(&app.world);
+        let group = groups.iter().find(|group|group.agent == Agent::Player).unwrap();
+        let tile = group.list.iter()
+            .flat_map(|&(index, _)|grid.graph.neighbors(index).unwrap().iter().copied())
+            .find(|&index|grid.tiles[index].reference.is_none() && grid.tiles[index].flags & MapGrid::BLOCKER == 0)
+            .unwrap();
+        let bundle = app.world.resource::<AssetBundle<BlueprintAssetBundle>>();
+        let handle = bundle.find_unit(&"extractor".to_string()).clone();
+        app.world.send_event(InteractionEvent::Construct(Agent::Player, parent, tile, handle));
+        for _ in 0..TICKS { app.update(); }
+        let recorded = capture_match_state(&mut app);
+        let replay: Replay = app.world.resource::<ReplayRecorder>().replay.clone();
+        assert!(matches!(replay.entries.as_slice(), [ReplayEntry { action: ReplayAction::Construct { tile: index, .. }, .. }] if *index == tile));
+
+        let mut played: Vec<(Vec<GroupSummary>, Vec<(usize, i32, i32)>)> = Vec::new();
+        for _ in 0..2 {
+            let mut app = start_headless_app(|app|{ app.insert_resource(ReplayPlayback::from(replay.clone())); });
+            for _ in 0..TICKS { app.update(); }
+            assert!(app.world.resource::<ReplayPlayback>().has_ended());
+            played.push(capture_match_state(&mut app));
+        }
+        assert_eq!(played[0], played[1]);
+        assert_eq!(played[0], recorded);
+    }
+
+    fn capture_snapshot(app: &mut App) -> MatchSnapshot {
+        let mut system = IntoSystem::into_system(capture_match_snapshot);
+        system.initialize(&mut app.world);
+        let mut snapshot = system.run((), &mut app.world);
+        snapshot.stage.strategy.profiles.clear();
+        snapshot
+    }
+
+    #[test] pub fn snapshot_restores_match(){
+        let mut app = start_headless_app(|_|{});
+        app.world.resource_mut::<StrategySettings>().low_matter_threshold = 37;
+        for _ in 0..300 { app.update(); }
+        let saved = capture_snapshot(&mut app);
+        assert!(saved.areas.iter().any(|area|!area.units.is_empty()));
+        let text = serde_ron::ser::to_string(&saved).unwrap();
+
+        let snapshot: MatchSnapshot = serde_ron::de::from_bytes(text.as_bytes()).unwrap();
+        let mut app = start_headless_app(|app|{
+            app.insert_resource(PendingSnapshot(snapshot));
+            app.insert_resource(TimeUpdateStrategy::ManualDuration(std::time::Duration::ZERO));
+        });
+        assert_eq!(app.world.resource::<SimulationTick>().elapsed, saved.tick.elapsed);
+        assert_eq!(app.world.resource::<StrategySettings>().low_matter_threshold, 37);
+        let loaded = capture_snapshot(&mut app);
+        assert_eq!(serde_ron::ser::to_string(&loaded).unwrap(), text);
+    }
+}
@@ -1,6 +1,8 @@
 use bevy::prelude::*;
 use bevy::input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel};
 use bevy::window::PrimaryWindow;
+use crate::logic::SimulationSpeed;
+use crate::interaction::ViewMode;
 
 #[derive(Resource)]
 pub struct InputMapping {
@@ -9,6 +11,9 @@ pub struct InputMapping {
     rotate_sensitivity: f32,
     pan_toggle: MouseButton,
     rotate_toggle: MouseButton,
+    pause_toggle: KeyCode,
+    step_key: KeyCode,
+    speed_keys: [KeyCode; SimulationSpeed::LEVELS.len()],
 }
 
 #[derive(Resource, Clone, Default)]
@@ -75,6 +80,25 @@ pub fn handle_input_system(
     }
 }
 
+pub fn handle_speed_input(
+    keys: Res<Input<KeyCode>>,
+    options: Res<InputMapping>,
+    mode: Res<ViewMode>,
+    mut speed: ResMut<SimulationSpeed>,
+){
+    if let ViewMode::Menu | ViewMode::Editor = mode.as_ref() { return; }
+    if keys.just_pressed(options.pause_toggle) {
+        speed.paused = !speed.paused;
+    }
+    if keys.just_pressed(options.step_key) && speed.paused {
+        speed.steps += 1;
+    }
+    if let Some(level) = options.speed_keys.iter().position(|&key|keys.just_pressed(key)) {
+        speed.level = level;
+        speed.paused = false;
+    }
+}
+
 pub struct InputManagerPlugin;
 impl Plugin for InputManagerPlugin {
     fn build(&self, app: &mut App) {
@@ -86,7 +110,11 @@ impl Plugin for InputManagerPlugin {
             zoom_sensitivity: 0.1,
             pan_toggle: MouseButton::Middle,
             rotate_toggle: MouseButton::Left,
+            pause_toggle: KeyCode::Space,
+            step_key: KeyCode::Period,
+            speed_keys: [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4],
         })
-        .add_system(handle_input_system.in_base_set(CoreSet::PreUpdate));
+        .add_system(handle_input_system.in_base_set(CoreSet::PreUpdate))
+        .add_system(handle_speed_input.in_base_set(CoreSet::PreUpdate));
     }
 }
\ No newline at end of file
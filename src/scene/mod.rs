@@ -5,6 +5,10 @@ mod setup;
 mod camera;
 mod input;
 mod lighting;
+mod headless;
+mod snapshot;
+mod placement;
+mod editor;
 
 pub use bundles::blueprint::*;
 pub use bundles::environment::*;
@@ -12,8 +16,13 @@ pub use bundles::interface::*;
 pub use bundles::effects::*;
 pub use bundles::models::*;
 pub use bundles::audio::*;
+pub use headless::HeadlessSimulationPlugin;
+pub use snapshot::*;
+pub use placement::*;
+pub use editor::*;
 
 use bevy::prelude::*;
+use bevy::input::common_conditions::input_just_pressed;
 use crate::common::loader::{LoadingState, AssetBundle, RonAssetPlugin};
 
 #[derive(States, Clone, PartialEq, Eq, Hash, Default, Debug)]
@@ -21,6 +30,7 @@ pub enum GlobalState {
     #[default] Menu,
     Running,
     Paused,
+    Editor,
 }
 
 pub struct DemoPlugin; impl Plugin for DemoPlugin {
@@ -30,6 +40,7 @@ pub struct DemoPlugin; impl Plugin for DemoPlugin {
 
         app.add_plugin(input::InputManagerPlugin);
 
+        app.init_resource::<StageSelection>();
         app.init_resource::<AssetBundle<BlueprintAssetBundle>>();
         app.init_resource::<AssetBundle<EnvironmentAssetBundle>>();
         app.init_resource::<AssetBundle<EffectAssetBundle>>();
@@ -37,12 +48,24 @@ pub struct DemoPlugin; impl Plugin for DemoPlugin {
         app.init_resource::<AssetBundle<ModelAssetBundle>>();
         app.init_resource::<AssetBundle<AudioAssetBundle>>();
 
-        app.add_system(stage::load_stage.in_schedule(OnExit(GlobalState::Menu)));
+        app.add_system(stage::load_stage
+            .after(crate::logic::reset_simulation_tick)
+            .in_schedule(OnExit(GlobalState::Menu)));
         app.add_systems((
             apply_system_buffers, stage::unload_stage, apply_system_buffers,
         ).chain().in_schedule(OnEnter(GlobalState::Menu)));
+        app.add_system(snapshot::resume_match_snapshot
+            .run_if(resource_exists::<PendingSnapshot>())
+            .in_schedule(OnEnter(GlobalState::Menu)));
+        app.add_system(snapshot::capture_match_snapshot.pipe(snapshot::save_match_snapshot)
+            .run_if(input_just_pressed(KeyCode::F5))
+            .in_set(OnUpdate(GlobalState::Running)));
+        app.add_system(snapshot::load_match_snapshot.in_set(OnUpdate(LoadingState::Running)));
         app.add_system(theme::update_theme.in_set(OnUpdate(LoadingState::Running)));
         app.add_system(lighting::update_orbiting_transforms.in_set(OnUpdate(GlobalState::Running)));
+        app.add_systems((
+            editor::update_stage_editor, editor::rebuild_stage_editor,
+        ).chain().in_set(OnUpdate(GlobalState::Editor)));
         app.add_startup_system(setup::setup_scene);
 
         app.add_system(camera::update_camera_view.in_base_set(CoreSet::PreUpdate).after(input::handle_input_system));
@@ -0,0 +1,170 @@
+use crate::common::adjacency::{dijkstra_distance_field, TraversableGraph};
+use crate::logic::{Agent, MapGrid, GlobalEconomy, TerrainVariantTable};
+use super::UnitPlacement;
+
+///Procedural start positions, placed in addition to the fixed stage units.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default, Debug)]
+pub struct StartPlacement {
+    pub area: usize,
+    pub agents: Vec<Agent>,
+    ///Unit keys placed on the start tile and the closest free tiles around it.
+    pub kit: Vec<String>,
+    ///Tile distance used to measure resource density around the start.
+    #[serde(default = "StartPlacement::default_radius")] pub radius: usize,
+    ///Penalty per unit of density difference between the richest and poorest start.
+    #[serde(default = "StartPlacement::default_balance")] pub balance: f32,
+}
+
+impl StartPlacement {
+    fn default_radius() -> usize { 2 }
+    ///Placement indices reserved for the kit of every agent.
+    pub fn slots(&self) -> usize { self.agents.len() * self.kit.len() }
+    fn default_balance() -> f32 { 1.0 }
+    fn is_free(grid: &MapGrid, terrain: &TerrainVariantTable, index: usize) -> bool {
+        grid.tiles[index].flags & MapGrid::BLOCKER == 0 && grid.tiles[index].reference.is_none() &&
+        terrain.buildable(grid.tiles[index].variant)
+    }
+    fn density(&self, grid: &MapGrid, economy: &GlobalEconomy, terrain: &TerrainVariantTable, index: usize) -> i32 {
+        grid.graph.iter_breadth_first().with_origin(index).with_limit(self.radius)
+        .filter(|&adjacent|Self::is_free(grid, terrain, adjacent))
+        .map(|adjacent|economy.density.get(grid.tiles[adjacent].variant).copied().unwrap_or_default())
+        .sum()
+    }
+    ///Start tiles maximizing the distance between agents while keeping resource density even.
+    pub fn select_tiles(&self, grid: &MapGrid, economy: &GlobalEconomy, terrain: &TerrainVariantTable) -> Vec<usize> {
+        if self.agents.is_empty() { return Vec::new(); }
+        let candidates: Vec<usize> = (0..grid.tiles.len())
+        .filter(|&index|Self::is_free(grid, terrain, index))
+        .filter(|&index|grid.graph.iter_breadth_first().with_origin(index).with_limit(1)
+            .filter(|&adjacent|Self::is_free(grid, terrain, adjacent))
+            .count() > self.kit.len().min(6))
+        .collect();
+        let density: Vec<i32> = candidates.iter().map(|&index|self.density(grid, economy, terrain, index)).collect();
+        let distances: Vec<Vec<f32>> = candidates.iter().map(|&index|{
+            let field = dijkstra_distance_field(
+                &grid.graph, [index],
+                |_, adjacent|if grid.tiles[adjacent].flags & MapGrid::BLOCKER == 0 { Some(1.0) }else{ None },
+                None
+            );
+            candidates.iter().map(|&target|field.distance(target).unwrap_or(f32::MAX)).collect()
+        }).collect();
+
+        let mut best: Option<(f32, Vec<usize>)> = None;
+        for first in 0..candidates.len() {
+            let mut selected: Vec<usize> = vec![first];
+            while selected.len() < self.agents.len() {
+                let Some(next) = (0..candidates.len())
+                .filter(|index|!selected.contains(index))
+                .map(|index|(index, selected.iter().map(|&other|distances[other][index]).fold(f32::MAX, f32::min)))
+                .filter(|(_, distance)|*distance < f32::MAX)
+                .max_by(|a, b|a.1.total_cmp(&b.1)
+                    .then_with(||(density[a.0] - density[first]).abs().cmp(&(density[b.0] - density[first]).abs()).reverse())
+                ) else { break };
+                selected.push(next.0);
+            }
+            if selected.len() < self.agents.len() { continue; }
+            let spread = selected.iter().map(|&index|density[index]).max().unwrap_or_default()
+                - selected.iter().map(|&index|density[index]).min().unwrap_or_default();
+            let separation = selected.iter().enumerate()
+                .flat_map(|(i, &a)|selected[i+1..].iter().map(move |&b|(a, b)))
+                .map(|(a, b)|distances[a][b])
+                .fold(f32::MAX, f32::min);
+            let score = separation.min(grid.tiles.len() as f32) - self.balance * spread as f32;
+            if best.as_ref().map_or(true, |(best_score, _)|score > *best_score) {
+                best = Some((score, selected));
+            }
+        }
+        best.map_or_else(Vec::new, |(_, selected)|selected.into_iter().map(|index|candidates[index]).collect())
+    }
+    ///Unit placements of the starting kit for each agent, keyed by agent and kit slot.
+    pub fn resolve(&self, grid: &MapGrid, economy: &GlobalEconomy, terrain: &TerrainVariantTable) -> Vec<(usize, UnitPlacement)> {
+        let mut occupied: Vec<usize> = Vec::new();
+        let mut placements: Vec<(usize, UnitPlacement)> = Vec::new();
+        for (agent_slot, (agent, start)) in self.agents.iter().zip(self.select_tiles(grid, economy, terrain).into_iter()).enumerate() {
+            let mut tiles = grid.graph.iter_breadth_first().with_origin(start)
+            .with_filter(|index|grid.tiles[index].flags & MapGrid::BLOCKER == 0)
+            .filter(|&index|Self::is_free(grid, terrain, index));
+            for (kit_slot, key) in self.kit.iter().enumerate() {
+                let Some(tile) = tiles.find(|index|!occupied.contains(index)) else { break };
+                occupied.push(tile);
+                placements.push((
+                    agent_slot * self.kit.len() + kit_slot,
+                    UnitPlacement { key: key.clone(), area: self.area, tile, agent: *agent }
+                ));
+            }
+        }
+        placements
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::AreaBlueprint;
+
+    fn load_grid() -> MapGrid {
+        let area: AreaBlueprint = serde_ron::de::from_str("AreaBlueprint(
+            seed: 4099, center: (0.0, 0.0, 0.0), resolution: 4,
+            variants: [0.35,0.65,0.85,1.0], noise_frequency: 0.05, noise_octaves: 4,
+            rules: [Ridges(count: 4, length: 8, variant: Some(2)), Deposits(count: 9, spacing: 4, variant: 3)],
+        )").unwrap();
+        area.load().3
+    }
+    fn start_placement(balance: f32) -> StartPlacement {
+        StartPlacement {
+            area: 0, agents: vec![Agent::Player, Agent::AI(1), Agent::AI(2)],
+            kit: vec!["vessel".to_string(), "extractor".to_string()],
+            radius: StartPlacement::default_radius(), balance,
+        }
+    }
+    fn economy() -> GlobalEconomy { GlobalEconomy { density: vec![1, 2, 0, 6], ..Default::default() } }
+    fn separation(grid: &MapGrid, tiles: &[usize]) -> f32 {
+        tiles.iter().enumerate().flat_map(|(i, &a)|tiles[i+1..].iter().map(move |&b|(a, b))).map(|(a, b)|dijkstra_distance_field(
+            &grid.graph, [a], |_, adjacent|if grid.tiles[adjacent].flags & MapGrid::BLOCKER == 0 { Some(1.0) }else{ None }, None
+        ).distance(b).unwrap_or(f32::MAX)).fold(f32::MAX, f32::min)
+    }
+
+    #[test]
+    pub fn starts_avoid_blockers(){
+        let grid = load_grid();
+        assert!(grid.tiles.iter().any(|tile|tile.flags & MapGrid::BLOCKER != 0));
+        let starts = start_placement(1.0);
+        let terrain = TerrainVariantTable::default();
+        let tiles = starts.select_tiles(&grid, &economy(), &terrain);
+        assert_eq!(tiles.len(), starts.agents.len());
+        let placements = starts.resolve(&grid, &economy(), &terrain);
+        assert_eq!(placements.len(), starts.slots());
+        for &(_, ref placement) in placements.iter() {
+            assert_eq!(grid.tiles[placement.tile].flags & MapGrid::BLOCKER, 0);
+        }
+        let slots: Vec<usize> = placements.iter().map(|(slot, _)|*slot).collect();
+        assert_eq!(slots, (0..starts.slots()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    pub fn starts_are_spread_apart(){
+        let grid = load_grid();
+        let terrain = TerrainVariantTable::default();
+        let starts = start_placement(1.0);
+        let tiles = starts.select_tiles(&grid, &economy(), &terrain);
+        let trivial: Vec<usize> = (0..grid.tiles.len())
+            .filter(|&index|StartPlacement::is_free(&grid, &terrain, index))
+            .take(starts.agents.len()).collect();
+        assert!(separation(&grid, &tiles) > separation(&grid, &trivial));
+    }
+
+    #[test]
+    pub fn density_spread_is_bounded(){
+        let grid = load_grid();
+        let terrain = TerrainVariantTable::default();
+        let spread = |balance: f32|{
+            let starts = start_placement(balance);
+            let density: Vec<i32> = starts.select_tiles(&grid, &economy(), &terrain).into_iter()
+                .map(|index|starts.density(&grid, &economy(), &terrain, index)).collect();
+            density.iter().max().unwrap() - density.iter().min().unwrap()
+        };
+        let (balanced, unbalanced) = (spread(100.0), spread(0.0));
+        assert!(balanced <= unbalanced);
+        assert!(balanced <= 2, "density spread {}", balanced);
+    }
+}
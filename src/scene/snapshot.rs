@@ -0,0 +1,308 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use std::path::Path;
+use crate::common::loader::AssetBundle;
+use crate::logic::{Agent, MapGrid, GridTileIndex, GridAreaIndex, PriorityOrder, PriorityLevel, GroupLink, GlobalEconomy, StrategySettings, SimulationTick, AreaBlueprint};
+use crate::logic::{StagePlacement, MatchOutcome, TriggerState, GridVisibility};
+use crate::logic::{Integrity, UnderConstruction, QueuedConstruction, MatterBinding, Suspended, FabricationGate, FollowingPath, LandingProbe, MatterCarrier, ArmyReserve, MilitarySupply, MilitaryBinding};
+use crate::logic::ConstructionEvent;
+use crate::interaction::{construct_structure, construct_unit};
+use super::{GlobalState, StageBlueprint, StageSelection, UnitBlueprint, BlueprintAssetBundle, ModelAssetBundle};
+
+pub const SNAPSHOT_PATH: &str = "quicksave.ron";
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+pub struct IntegritySnapshot {
+    pub max: i32,
+    pub rate: i32,
+    pub absorbed: i32,
+    pub restored: i32,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+pub struct GateSnapshot {
+    pub filter: u8,
+    pub released: i32,
+    pub limit: i32,
+    pub path: Vec<usize>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+pub struct PathSnapshot {
+    pub path: Vec<usize>,
+    pub elapsed: f32,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+pub struct SupplySnapshot {
+    pub range: i32,
+    pub amplitude: i32,
+    pub frequency: i32,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+pub struct UnitSnapshot {
+    pub key: String,
+    pub agent: Agent,
+    pub tile: usize,
+    pub placement: Option<usize>,
+    ///Structures keep their priority order, mobile units have none.
+    pub priority: Option<u64>,
+    pub linked: bool,
+    pub integrity: IntegritySnapshot,
+    pub construction: Option<i32>,
+    pub matter: Option<MatterBinding>,
+    pub suspended: bool,
+    #[serde(default)] pub queued: bool,
+    #[serde(default)] pub level: PriorityLevel,
+    pub gate: Option<GateSnapshot>,
+    pub path: Option<PathSnapshot>,
+    pub landing: bool,
+    #[serde(default)] pub carrier: bool,
+    #[serde(default)] pub reserve: bool,
+    pub supply: Option<SupplySnapshot>,
+    ///Matter stored in the barrier, other military bindings carry no state.
+    #[serde(default)] pub barrier: Option<i32>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+pub struct TileSnapshot {
+    pub variant: usize,
+    pub flags: u8,
+    ///Index into the unit list of the area.
+    pub reference: Option<usize>,
+    #[serde(default)] pub visible: u32,
+    #[serde(default)] pub explored: u32,
+    #[serde(default)] pub revealed: u32,
+    #[serde(default)] pub sighted: u32,
+    #[serde(default)] pub reserve: Option<i32>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+pub struct AreaSnapshot {
+    pub tiles: Vec<TileSnapshot>,
+    pub units: Vec<UnitSnapshot>,
+}
+
+///Running match state, the stage blueprint holds the terrain of each area while unit placements are left empty.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+pub struct MatchSnapshot {
+    pub stage: StageBlueprint,
+    pub priority: u64,
+    pub tick: SimulationTick,
+    pub outcome: MatchOutcome,
+    #[serde(default)] pub triggers: TriggerState,
+    pub areas: Vec<AreaSnapshot>,
+}
+impl MatchSnapshot {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let bytes = std::fs::read(path)?;
+        Ok(serde_ron::de::from_bytes(&bytes)?)
+    }
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn std::error::Error>> {
+        let text = serde_ron::ser::to_string_pretty(self, Default::default())?;
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+///Snapshot to restore instead of the intro stage on the next exit from menu.
+#[derive(Resource, Deref, Clone)]
+pub struct PendingSnapshot(pub MatchSnapshot);
+
+pub fn capture_match_snapshot(
+    global: Res<GlobalEconomy>,
+    strategy: Res<StrategySettings>,
+    tick: Res<SimulationTick>,
+    outcome: Res<MatchOutcome>,
+    triggers: Res<TriggerState>,
+    blueprints: Res<Assets<UnitBlueprint>>,
+    stages: Res<Assets<StageBlueprint>>,
+    selection: Res<StageSelection>,
+    blueprint_bundle: Res<AssetBundle<BlueprintAssetBundle>>,
+    query_grid: Query<(&GridAreaIndex, &AreaBlueprint, &MapGrid, Option<&GridVisibility>, &Children)>,
+    query_unit: Query<(
+        Entity, &Handle<UnitBlueprint>, &Agent, &GridTileIndex, &Integrity,
+        (Option<&PriorityOrder>, Option<&PriorityLevel>), Option<&GroupLink>, Option<&UnderConstruction>, Option<&MatterBinding>,
+        (Option<&Suspended>, Option<&QueuedConstruction>), Option<&FabricationGate>, Option<&FollowingPath>, (Option<&LandingProbe>, Option<&MatterCarrier>, Option<&ArmyReserve>), (Option<&MilitarySupply>, Option<&MilitaryBinding>), Option<&StagePlacement>
+    )>,
+) -> MatchSnapshot {
+    let mut areas: Vec<(usize, AreaBlueprint, AreaSnapshot)> = Vec::new();
+    for (area_index, area, grid, visibility, children) in query_grid.iter() {
+        let mut mapping: HashMap<Entity, usize> = HashMap::default();
+        let mut units: Vec<UnitSnapshot> = Vec::new();
+        for (
+            entity, handle, agent, tile_index, integrity,
+            (order, level), link, construction, matter, (suspended, queued), gate, movement, (probe, carrier, reserve), (supply, military), placement
+        ) in query_unit.iter_many(children) {
+            let Some(blueprint) = blueprints.get(handle) else { continue };
+            mapping.insert(entity, units.len());
+            units.push(UnitSnapshot {
+                key: blueprint.key.clone(),
+                agent: *agent,
+                tile: **tile_index,
+                placement: placement.map(|placement|**placement),
+                priority: order.map(|order|order.0),
+                linked: link.is_some(),
+                integrity: IntegritySnapshot {
+                    max: integrity.max, rate: integrity.rate,
+                    absorbed: integrity.absorbed, restored: integrity.restored,
+                },
+                construction: construction.map(|construction|construction.matter_consumed),
+                matter: matter.cloned(),
+                suspended: suspended.is_some(),
+                queued: queued.is_some(),
+                level: level.copied().unwrap_or_default(),
+                gate: gate.map(|gate|GateSnapshot {
+                    filter: gate.filter, released: gate.released, limit: gate.limit, path: gate.path.clone(),
+                }),
+                path: movement.map(|movement|PathSnapshot {
+                    path: movement.path.clone(), elapsed: movement.elapsed,
+                }),
+                landing: probe.is_some(),
+                carrier: carrier.is_some(),
+                reserve: reserve.is_some(),
+                supply: supply.filter(|supply|supply.snapshot).map(|supply|SupplySnapshot {
+                    range: supply.range, amplitude: supply.amplitude, frequency: supply.frequency,
+                }),
+                barrier: match military {
+                    Some(MilitaryBinding::Barrier { stored, .. }) => Some(*stored),
+                    _ => None
+                },
+            });
+        }
+        let tiles = grid.tiles.iter().enumerate().map(|(index, tile)|TileSnapshot {
+            variant: tile.variant, flags: tile.flags,
+            reference: tile.reference.and_then(|entity|mapping.get(&entity).copied()),
+            visible: visibility.and_then(|visibility|visibility.visible.get(index).copied()).unwrap_or_default(),
+            explored: visibility.and_then(|visibility|visibility.explored.get(index).copied()).unwrap_or_default(),
+            revealed: visibility.and_then(|visibility|visibility.revealed.get(index).copied()).unwrap_or_default(),
+            sighted: visibility.and_then(|visibility|visibility.sighted.get(index).copied()).unwrap_or_default(),
+            reserve: (tile.capacity > 0).then_some(tile.reserve),
+        }).collect();
+        areas.push((**area_index, area.clone(), AreaSnapshot { tiles, units }));
+    }
+    areas.sort_by_key(|(area_index, _, _)|*area_index);
+
+    MatchSnapshot {
+        stage: StageBlueprint {
+            economy: global.clone(),
+            strategy: strategy.clone(),
+            areas: areas.iter().map(|(_, area, _)|area.clone()).collect(),
+            units: Vec::new(),
+            ..blueprint_bundle.stages.get(**selection).and_then(|handle|stages.get(handle)).cloned().unwrap_or_default()
+        },
+        priority: global.priority,
+        tick: *tick,
+        outcome: outcome.clone(),
+        triggers: triggers.clone(),
+        areas: areas.into_iter().map(|(_, _, area)|area).collect(),
+    }
+}
+
+pub fn save_match_snapshot(In(snapshot): In<MatchSnapshot>){
+    if let Err(error) = snapshot.save(SNAPSHOT_PATH) {
+        error!("failed to save snapshot {}: {}", SNAPSHOT_PATH, error);
+    }
+}
+
+pub fn load_match_snapshot(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    state: Res<State<GlobalState>>,
+    mut next_state: ResMut<NextState<GlobalState>>,
+){
+    if !keys.just_pressed(KeyCode::F9) { return; }
+    let snapshot = match MatchSnapshot::load(SNAPSHOT_PATH) {
+        Ok(snapshot) => snapshot,
+        Err(error) => { error!("failed to load snapshot {}: {}", SNAPSHOT_PATH, error); return; }
+    };
+    commands.insert_resource(PendingSnapshot(snapshot));
+    next_state.set(if state.0 == GlobalState::Menu { GlobalState::Running }else{ GlobalState::Menu });
+}
+
+pub fn resume_match_snapshot(mut next_state: ResMut<NextState<GlobalState>>){
+    next_state.set(GlobalState::Running);
+}
+
+pub fn restore_stage_area(
+    commands: &mut Commands,
+    construction_events: &mut EventWriter<ConstructionEvent>,
+    parent: Entity, grid: &mut MapGrid,
+    model_bundle: Option<&AssetBundle<ModelAssetBundle>>,
+    blueprints: &Assets<UnitBlueprint>,
+    blueprint_bundle: &BlueprintAssetBundle,
+    area: &AreaSnapshot, tick: u64,
+){
+    let mut entities: Vec<Entity> = Vec::with_capacity(area.units.len());
+    for unit in area.units.iter() {
+        let handle = blueprint_bundle.find_unit(&unit.key).clone();
+        let blueprint = blueprints.get(&handle).unwrap();
+        let entity = if let Some(order) = unit.priority {
+            construct_structure(
+                commands, construction_events, parent, grid, model_bundle, blueprints,
+                (handle, unit.agent, unit.tile, order), unit.construction.is_none()
+            )
+        } else {
+            construct_unit(commands, parent, grid, model_bundle, blueprints, (handle, unit.agent, unit.tile))
+        };
+        entities.push(entity);
+
+        let mut commands = commands.entity(entity);
+        commands.insert(Integrity {
+            max: unit.integrity.max, rate: unit.integrity.rate,
+            prev_max: unit.integrity.max,
+            absorbed: unit.integrity.absorbed,
+            restored: unit.integrity.restored, prev_restored: unit.integrity.restored,
+            resistance: blueprint.integrity.resistance.clone(),
+        });
+        if let Some(matter_consumed) = unit.construction {
+            let mut construction = blueprint.construction.clone();
+            construction.matter_consumed = matter_consumed;
+            commands.insert(construction);
+        }
+        if let Some(matter) = unit.matter.as_ref() { commands.insert(matter.clone()); }
+        if !unit.linked { commands.remove::<GroupLink>(); }
+        if unit.suspended { commands.insert(Suspended); }
+        if unit.queued { commands.insert(QueuedConstruction); }
+        if unit.level != PriorityLevel::Normal { commands.insert(unit.level); }
+        if let Some(gate) = unit.gate.as_ref() {
+            commands.insert(FabricationGate {
+                filter: gate.filter, released: gate.released, limit: gate.limit,
+                path: gate.path.clone(), last_released: tick, ..Default::default()
+            });
+        }
+        if let Some(movement) = unit.path.as_ref() {
+            commands.insert(FollowingPath {
+                path: movement.path.clone(),
+                elapsed: movement.elapsed, prev_elapsed: movement.elapsed,
+                ..Default::default()
+            });
+        }
+        if unit.landing { commands.insert(LandingProbe); }
+        if unit.carrier { commands.insert(MatterCarrier); }
+        if unit.reserve { commands.insert(ArmyReserve); }
+        if let Some(placement) = unit.placement { commands.insert(StagePlacement(placement)); }
+        if let Some(supply) = unit.supply.as_ref() {
+            commands.insert(MilitarySupply {
+                range: supply.range, amplitude: supply.amplitude, frequency: supply.frequency, snapshot: true
+            });
+        }
+        if let (Some(barrier), Some(mut military)) = (unit.barrier, blueprint.military.clone()) {
+            if let MilitaryBinding::Barrier { stored, .. } = &mut military { *stored = barrier; }
+            commands.insert(military);
+        }
+    }
+    for (tile, snapshot) in grid.tiles.iter_mut().zip(area.tiles.iter()) {
+        tile.variant = snapshot.variant;
+        tile.flags = snapshot.flags;
+        tile.reference = snapshot.reference.map(|index|entities[index]);
+        if let Some(reserve) = snapshot.reserve { tile.reserve = reserve; }
+    }
+    commands.entity(parent).insert(GridVisibility {
+        visible: area.tiles.iter().map(|tile|tile.visible).collect(),
+        explored: area.tiles.iter().map(|tile|tile.explored).collect(),
+        revealed: area.tiles.iter().map(|tile|tile.revealed).collect(),
+        sighted: area.tiles.iter().map(|tile|tile.sighted).collect(),
+    });
+}
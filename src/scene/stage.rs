@@ -1,12 +1,14 @@
 use bevy::prelude::*;
 use bevy::core_pipeline::clear_color::ClearColorConfig;
 use crate::common::loader::AssetBundle;
-use super::{UnitBlueprint, BlueprintAssetBundle, StageBlueprint, EnvironmentAssetBundle, ModelAssetBundle};
-use crate::logic::{Agent, NetworkGroupList, ConstructionEvent};
+use super::{UnitBlueprint, UnitPlacement, BlueprintAssetBundle, StageBlueprint, StageSelection, EnvironmentAssetBundle, ModelAssetBundle, GlobalState};
+use super::StageEditor;
+use crate::logic::{Agent, AreaBlueprint, MapGrid, GridAreaIndex, NetworkGroupList, ConstructionEvent, SimulationTick, StagePlacement, MatchOutcome, GridVisibility, TriggerState};
 use crate::materials::SkyboxNebula;
 use crate::interaction::ViewMode;
 use crate::interaction::construct_structure;
 use super::lighting::setup_lighting;
+use super::snapshot::{PendingSnapshot, restore_stage_area};
 
 pub fn load_stage(
     mut commands: Commands,
@@ -16,15 +18,29 @@ pub fn load_stage(
     environment_bundle: Res<AssetBundle<EnvironmentAssetBundle>>,
     model_bundle: Res<AssetBundle<ModelAssetBundle>>,
     stages: Res<Assets<StageBlueprint>>,
+    selection: Res<StageSelection>,
+    snapshot: Option<Res<PendingSnapshot>>,
+    state: Res<State<GlobalState>>,
+    mut next_state: ResMut<NextState<GlobalState>>,
+    mut tick: ResMut<SimulationTick>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut mode: ResMut<ViewMode>,
     query_camera: Query<Entity, With<Camera3d>>,
 ){
-    let next_stage = stages.get(&blueprint_bundle.intro_stage).unwrap();
+    let Some(next_stage) = snapshot.as_ref().map(|snapshot|&snapshot.stage)
+        .or_else(||blueprint_bundle.stages.get(**selection).and_then(|handle|stages.get(handle))) else {
+        error!("no stage to load at index {}", **selection);
+        next_state.set(GlobalState::Menu);
+        return;
+    };
     setup_lighting(&mut commands, next_stage);
-    commands.insert_resource(next_stage.economy.clone());
-    commands.insert_resource(next_stage.strategy.clone());
-    *mode = ViewMode::Default(Agent::Player);
+    insert_stage_resources(&mut commands, next_stage, snapshot.as_deref(), &mut tick);
+    if state.0 == GlobalState::Editor {
+        *mode = ViewMode::Editor;
+        commands.insert_resource(StageEditor::from(next_stage.clone()));
+    } else {
+        *mode = ViewMode::Default(Agent::Player);
+    }
 
     let camera_entity = query_camera.get_single().unwrap();
     commands.entity(camera_entity).insert(SkyboxNebula {
@@ -36,31 +52,156 @@ pub fn load_stage(
 
 
     for (area_index, area) in next_stage.areas.iter().enumerate() {
-        let (mesh, transform, hitbox, mut grid) = area.load();
+        let (entity, mut grid) = spawn_stage_area(&mut commands, &mut meshes, &environment_bundle, area, area_index);
+
+        if let Some(snapshot) = snapshot.as_ref() {
+            restore_stage_area(
+                &mut commands, &mut construction_events, entity, &mut grid, Some(&*model_bundle), &blueprints,
+                &blueprint_bundle, &snapshot.areas[area_index], tick.elapsed
+            );
+        } else {
+            populate_stage_area(
+                &mut commands, &mut construction_events, entity, &mut grid, Some(&*model_bundle), &blueprints,
+                &blueprint_bundle, next_stage, area_index
+            );
+        }
+        commands.entity(entity).insert(grid);
+    }
+}
+
+pub fn load_stage_headless(
+    mut commands: Commands,
+    mut construction_events: EventWriter<ConstructionEvent>,
+    blueprints: Res<Assets<UnitBlueprint>>,
+    blueprint_bundle: Res<AssetBundle<BlueprintAssetBundle>>,
+    stages: Res<Assets<StageBlueprint>>,
+    selection: Res<StageSelection>,
+    snapshot: Option<Res<PendingSnapshot>>,
+    mut next_state: ResMut<NextState<GlobalState>>,
+    mut tick: ResMut<SimulationTick>,
+){
+    let Some(next_stage) = snapshot.as_ref().map(|snapshot|&snapshot.stage)
+        .or_else(||blueprint_bundle.stages.get(**selection).and_then(|handle|stages.get(handle))) else {
+        error!("no stage to load at index {}", **selection);
+        next_state.set(GlobalState::Menu);
+        return;
+    };
+    insert_stage_resources(&mut commands, next_stage, snapshot.as_deref(), &mut tick);
+
+    for (area_index, area) in next_stage.areas.iter().enumerate() {
+        let (_, transform, hitbox, mut grid) = area.load();
 
         let entity = commands.spawn((
-            MaterialMeshBundle {
-                mesh: meshes.add(mesh),
-                material: environment_bundle.terrain_material.clone(),
-                transform, ..Default::default()
-            },
-            // crate::common::raycast::RaycastTarget::default(),
-            hitbox, area.clone(),
-            NetworkGroupList::default(),
-            crate::effects::linker::TileConnectors::default()
+            SpatialBundle::from_transform(transform),
+            hitbox, area.clone(), GridAreaIndex(area_index),
+            NetworkGroupList::default(), GridVisibility::default(),
         )).id();
 
-        for placement in next_stage.units.iter().filter(|placement| placement.area == area_index) {
-            let handle = blueprint_bundle.find_unit(&placement.key);
-            construct_structure(
-                &mut commands, &mut construction_events, entity, &mut grid, &model_bundle, &blueprints,
-                (handle.clone(), placement.agent, placement.tile, 0), true
+        if let Some(snapshot) = snapshot.as_ref() {
+            restore_stage_area(
+                &mut commands, &mut construction_events, entity, &mut grid, None, &blueprints,
+                &blueprint_bundle, &snapshot.areas[area_index], tick.elapsed
+            );
+        } else {
+            populate_stage_area(
+                &mut commands, &mut construction_events, entity, &mut grid, None, &blueprints,
+                &blueprint_bundle, next_stage, area_index
             );
         }
         commands.entity(entity).insert(grid);
     }
 }
 
+pub fn spawn_stage_area(
+    commands: &mut Commands, meshes: &mut Assets<Mesh>,
+    environment_bundle: &EnvironmentAssetBundle,
+    area: &AreaBlueprint, area_index: usize,
+) -> (Entity, MapGrid) {
+    let (mesh, transform, hitbox, grid) = area.load();
+    let entity = commands.spawn((
+        MaterialMeshBundle {
+            mesh: meshes.add(mesh),
+            material: environment_bundle.terrain_material.clone(),
+            transform, ..Default::default()
+        },
+        // crate::common::raycast::RaycastTarget::default(),
+        hitbox, area.clone(), GridAreaIndex(area_index),
+        NetworkGroupList::default(), GridVisibility::default(),
+        crate::effects::linker::TileConnectors::default()
+    )).id();
+    (entity, grid)
+}
+
+fn insert_stage_resources(
+    commands: &mut Commands, stage: &StageBlueprint,
+    snapshot: Option<&PendingSnapshot>, tick: &mut SimulationTick,
+){
+    let mut economy = stage.economy.clone();
+    let mut outcome = MatchOutcome::default();
+    let mut triggers = TriggerState { spawn_offset: stage.spawn_offset(), ..Default::default() };
+    let mut strategy = stage.strategy.clone();
+    if let Some(snapshot) = snapshot {
+        strategy = snapshot.stage.strategy.clone();
+        economy.priority = snapshot.priority;
+        outcome = snapshot.outcome.clone();
+        triggers = snapshot.triggers.clone();
+        *tick = snapshot.tick;
+        commands.remove_resource::<PendingSnapshot>();
+    }
+    commands.insert_resource(economy);
+    commands.insert_resource(outcome);
+    commands.insert_resource(strategy);
+    commands.insert_resource(stage.objectives.clone());
+    commands.insert_resource(stage.triggers.clone());
+    commands.insert_resource(triggers);
+    commands.insert_resource(stage.alliances.clone());
+    commands.insert_resource(stage.terrain.clone());
+}
+
+pub fn populate_stage_area(
+    commands: &mut Commands,
+    construction_events: &mut EventWriter<ConstructionEvent>,
+    parent: Entity, grid: &mut MapGrid,
+    model_bundle: Option<&AssetBundle<ModelAssetBundle>>,
+    blueprints: &Assets<UnitBlueprint>,
+    blueprint_bundle: &BlueprintAssetBundle,
+    stage: &StageBlueprint, area_index: usize,
+){
+    let fixed = stage.units.iter().enumerate().filter(|(_, placement)| placement.area == area_index);
+    place_stage_units(commands, construction_events, parent, grid, model_bundle, blueprints, blueprint_bundle, fixed);
+    let starts = resolve_stage_starts(grid, stage, area_index);
+    place_stage_units(
+        commands, construction_events, parent, grid, model_bundle, blueprints, blueprint_bundle,
+        starts.iter().map(|(index, placement)|(*index, placement))
+    );
+}
+
+///Procedural start placements of the area, indexed after the fixed stage units.
+pub fn resolve_stage_starts(grid: &MapGrid, stage: &StageBlueprint, area_index: usize) -> Vec<(usize, UnitPlacement)> {
+    let Some(starts) = stage.starts.as_ref().filter(|starts|starts.area == area_index) else { return Vec::new() };
+    starts.resolve(grid, &stage.economy, &stage.terrain).into_iter()
+    .map(|(slot, placement)|(stage.units.len() + slot, placement)).collect()
+}
+
+pub fn place_stage_units<'a>(
+    commands: &mut Commands,
+    construction_events: &mut EventWriter<ConstructionEvent>,
+    parent: Entity, grid: &mut MapGrid,
+    model_bundle: Option<&AssetBundle<ModelAssetBundle>>,
+    blueprints: &Assets<UnitBlueprint>,
+    blueprint_bundle: &BlueprintAssetBundle,
+    placements: impl Iterator<Item = (usize, &'a UnitPlacement)>,
+){
+    for (index, placement) in placements {
+        let handle = blueprint_bundle.find_unit(&placement.key);
+        let entity = construct_structure(
+            commands, construction_events, parent, grid, model_bundle, blueprints,
+            (handle.clone(), placement.agent, placement.tile, 0), true
+        );
+        commands.entity(entity).insert(StagePlacement(index));
+    }
+}
+
 pub fn unload_stage(
     mut commands: Commands,
     query: Query<Entity, Or<(With<NetworkGroupList>, With<PointLight>)>>